@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bevy::gltf::Gltf;
+use bevy::prelude::*;
+
+use crate::app::{DamageEvent, DamageKind, Dying, PlayState, Respawning, ShotEvent};
+
+/// Ground speeds, in units per second, where walking and running start.
+const WALK_SPEED: f32 = 0.5;
+const RUN_SPEED: f32 = 8.0;
+/// Moving further than this in one frame is a teleport (respawn, origin
+/// rebase), not movement.
+const TELEPORT_DISTANCE: f32 = 5.0;
+/// Smooths the measured speed so a single still frame doesn't drop to idle.
+const SPEED_SMOOTHING: f32 = 12.0;
+const BLEND_SECS: f32 = 0.15;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AnimState {
+    Idle,
+    Walk,
+    Run,
+    Attack,
+    Hit,
+    Death,
+}
+
+impl AnimState {
+    const ALL: [AnimState; 6] = [
+        AnimState::Idle,
+        AnimState::Walk,
+        AnimState::Run,
+        AnimState::Attack,
+        AnimState::Hit,
+        AnimState::Death,
+    ];
+
+    /// Clip name in the character GLBs.
+    fn clip_name(self) -> &'static str {
+        match self {
+            AnimState::Idle => "idle",
+            AnimState::Walk => "walk",
+            AnimState::Run => "sprint",
+            AnimState::Attack => "holding-right-shoot",
+            // The kit has no hit reaction; a quick head shake reads as a flinch
+            AnimState::Hit => "emote-no",
+            AnimState::Death => "die",
+        }
+    }
+
+    fn looping(self) -> bool {
+        matches!(self, AnimState::Idle | AnimState::Walk | AnimState::Run)
+    }
+
+    fn speed(self) -> f32 {
+        match self {
+            AnimState::Hit => 2.0,
+            _ => 1.0,
+        }
+    }
+
+    /// How long a one-shot plays over the locomotion cycle.
+    fn one_shot_secs(self) -> f32 {
+        match self {
+            AnimState::Attack => 0.25,
+            AnimState::Hit => 0.3,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Graph built from one model's clips, shared by every character using it.
+struct ModelAnimations {
+    graph: Handle<AnimationGraph>,
+    nodes: HashMap<AnimState, AnimationNodeIndex>,
+}
+
+#[derive(Resource, Default)]
+pub struct CharacterAnimations {
+    gltfs: HashMap<String, Handle<Gltf>>,
+    models: HashMap<String, ModelAnimations>,
+}
+
+/// Picks the animation a character's model plays from how it moves and what
+/// happens to it.
+#[derive(Component)]
+pub struct CharacterAnimator {
+    model: String,
+    /// The scene's `AnimationPlayer`, once the scene has spawned.
+    player: Option<Entity>,
+    /// What is playing; `None` forces the next state to (re)start.
+    state: Option<AnimState>,
+    /// An attack or hit playing over locomotion.
+    one_shot: Option<(AnimState, Timer)>,
+    last_position: Option<Vec3>,
+    speed: f32,
+}
+
+impl CharacterAnimator {
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            player: None,
+            state: None,
+            one_shot: None,
+            last_position: None,
+            speed: 0.0,
+        }
+    }
+
+    fn trigger(&mut self, state: AnimState) {
+        self.one_shot = Some((state, Timer::from_seconds(state.one_shot_secs(), TimerMode::Once)));
+        self.state = None;
+    }
+}
+
+pub struct CharacterAnimationPlugin;
+
+impl Plugin for CharacterAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<CharacterAnimations>()
+            .add_systems(Update, (
+                build_animation_graphs,
+                link_animation_players,
+                trigger_one_shots,
+                drive_animations,
+            ).chain().run_if(in_state(PlayState::Running)));
+    }
+}
+
+/// Builds each model's graph from its named clips once the GLB has loaded.
+fn build_animation_graphs(
+    asset_server: Res<AssetServer>,
+    gltf_assets: Res<Assets<Gltf>>,
+    mut graphs: ResMut<Assets<AnimationGraph>>,
+    mut animations: ResMut<CharacterAnimations>,
+    animator_query: Query<&CharacterAnimator, Added<CharacterAnimator>>,
+) {
+    let animations = &mut *animations;
+    for animator in &animator_query {
+        if !animations.gltfs.contains_key(&animator.model) {
+            animations.gltfs.insert(animator.model.clone(), asset_server.load(animator.model.clone()));
+        }
+    }
+
+    for (model, handle) in &animations.gltfs {
+        if animations.models.contains_key(model) {
+            continue;
+        }
+        let Some(gltf) = gltf_assets.get(handle) else {
+            continue;
+        };
+        // Models missing a clip just fall back to idle for that state
+        let states: Vec<(AnimState, Handle<AnimationClip>)> = AnimState::ALL
+            .into_iter()
+            .filter_map(|state| gltf.named_animations.get(state.clip_name()).map(|clip| (state, clip.clone())))
+            .collect();
+        let (graph, indices) = AnimationGraph::from_clips(states.iter().map(|(_, clip)| clip.clone()));
+        animations.models.insert(model.clone(), ModelAnimations {
+            graph: graphs.add(graph),
+            nodes: states.into_iter().map(|(state, _)| state).zip(indices).collect(),
+        });
+    }
+}
+
+/// Finds the `AnimationPlayer` the scene spawns under each character and
+/// hooks the model's graph up to it.
+fn link_animation_players(
+    mut commands: Commands,
+    animations: Res<CharacterAnimations>,
+    mut animator_query: Query<(Entity, &mut CharacterAnimator)>,
+    children_query: Query<&Children>,
+    player_query: Query<(), With<AnimationPlayer>>,
+) {
+    for (entity, mut animator) in &mut animator_query {
+        if animator.player.is_some() {
+            continue;
+        }
+        let Some(model) = animations.models.get(&animator.model) else {
+            continue;
+        };
+        let Some(player) = children_query.iter_descendants(entity).find(|child| player_query.contains(*child)) else {
+            continue;
+        };
+        commands.entity(player).insert((
+            AnimationGraphHandle(model.graph.clone()),
+            AnimationTransitions::new(),
+        ));
+        animator.player = Some(player);
+        animator.state = None;
+    }
+}
+
+fn trigger_one_shots(
+    mut shot_events: EventReader<ShotEvent>,
+    mut damage_events: EventReader<DamageEvent>,
+    mut animator_query: Query<&mut CharacterAnimator>,
+) {
+    for event in shot_events.read() {
+        if let Ok(mut animator) = animator_query.get_mut(event.shooter) {
+            animator.trigger(AnimState::Attack);
+        }
+    }
+    for event in damage_events.read() {
+        // Lingering damage would keep the character flinching
+        if event.fatal || event.kind == DamageKind::OverTime {
+            continue;
+        }
+        if let Ok(mut animator) = animator_query.get_mut(event.target) {
+            animator.trigger(AnimState::Hit);
+        }
+    }
+}
+
+fn drive_animations(
+    time: Res<Time>,
+    animations: Res<CharacterAnimations>,
+    mut animator_query: Query<(&mut CharacterAnimator, &Transform, Has<Respawning>, Has<Dying>)>,
+    mut player_query: Query<(&mut AnimationPlayer, &mut AnimationTransitions)>,
+) {
+    let dt = time.delta_secs();
+    if dt <= 0.0 {
+        return;
+    }
+
+    for (mut animator, transform, respawning, dying) in &mut animator_query {
+        let position = transform.translation.with_y(0.0);
+        if let Some(last) = animator.last_position {
+            let moved = position.distance(last);
+            if moved < TELEPORT_DISTANCE {
+                let blend = 1.0 - (-SPEED_SMOOTHING * dt).exp();
+                animator.speed += (moved / dt - animator.speed) * blend;
+            }
+        }
+        animator.last_position = Some(position);
+
+        if let Some((_, timer)) = &mut animator.one_shot {
+            if timer.tick(time.delta()).finished() {
+                animator.one_shot = None;
+            }
+        }
+
+        let desired = if respawning || dying {
+            AnimState::Death
+        } else if let Some((state, _)) = &animator.one_shot {
+            *state
+        } else if animator.speed >= RUN_SPEED {
+            AnimState::Run
+        } else if animator.speed >= WALK_SPEED {
+            AnimState::Walk
+        } else {
+            AnimState::Idle
+        };
+        if animator.state == Some(desired) {
+            continue;
+        }
+
+        let (Some(player), Some(model)) = (animator.player, animations.models.get(&animator.model)) else {
+            continue;
+        };
+        let Ok((mut player, mut transitions)) = player_query.get_mut(player) else {
+            continue;
+        };
+        let Some(node) = model.nodes.get(&desired).or_else(|| model.nodes.get(&AnimState::Idle)) else {
+            continue;
+        };
+        let active = transitions
+            .play(&mut player, *node, Duration::from_secs_f32(BLEND_SECS))
+            .set_speed(desired.speed());
+        if desired.looping() {
+            active.repeat();
+        }
+        animator.state = Some(desired);
+    }
+}
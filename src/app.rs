@@ -1,9 +1,70 @@
 use bevy::prelude::*;
 use bevy::image::{ImageSampler, ImageSamplerDescriptor, ImageAddressMode, ImageLoaderSettings};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::animation::{CharacterAnimationPlugin, CharacterAnimator};
+use crate::audio::{audio_asset_paths, GameAudioPlugin};
+use crate::biome::{biome_asset_paths, default_fog, Biome, BiomePlugin, SpawnEntry, BIOME_SECTION_SEGMENTS};
+use crate::camera::CameraRigPlugin;
+use crate::daynight::{DayNightPlugin, TimeOfDay, NIGHT_ENEMY_HEALTH_MULTIPLIER, NIGHT_ENEMY_SCALE};
+use crate::feel::{GameFeelPlugin, Knockback};
+use crate::graphics::{window_to_viewport, GraphicsPlugin};
+use crate::hazards::HazardsPlugin;
+use crate::healthbar::HealthBarPlugin;
+use crate::input::{Action, ActionMap, Actions, InputMapPlugin};
+use crate::lane::{LaneLayout, LanePlugin, BUILDING_MODELS, LANE_MODELS};
+use crate::minimap::{over_minimap, MinimapInteraction, MinimapPlugin};
+use crate::numbers::{number_glyph_paths, FloatingNumbersPlugin};
+use crate::particles::{ParticlesPlugin, PARTICLE_EFFECT_PATHS};
+use crate::pause::PausePlugin;
+use crate::props::{Destructible, Empowered, PropsPlugin, EMPOWERED_DAMAGE_MULTIPLIER};
+use crate::settings::{SettingsMenu, SettingsPlugin};
+use crate::vision::{VisionGrid, VisionPlugin};
+use crate::weather::{CurrentWeather, WeatherPlugin};
 
 pub const LANE_HALF_WIDTH: f32 = 8.0;
 pub const PLAYER_BOUNDARY_Z: f32 = 7.0;
+pub const RESPAWN_DELAY_SECS: f32 = 3.0;
+pub const RESPAWN_INVULNERABILITY_SECS: f32 = 3.0;
+/// How long a fallen character stays visible, playing its death animation.
+pub const DEATH_ANIMATION_SECS: f32 = 1.0;
+/// How far behind the furthest progress point the player is put back down.
+pub const RESPAWN_BACKOFF: f32 = 5.0;
+/// Enemy projectiles within this distance of the respawn point are removed.
+pub const RESPAWN_CLEAR_RADIUS: f32 = 15.0;
+pub const PLAYER_MOVE_SPEED: f32 = 7.0;
+/// Collision radius of characters against lane obstacles.
+pub const CHARACTER_RADIUS: f32 = 0.6;
+pub const DASH_SPEED: f32 = 35.0;
+pub const DASH_DURATION_SECS: f32 = 0.2;
+pub const DASH_COOLDOWN_SECS: f32 = 5.0;
+pub const ROLL_SPEED: f32 = 18.0;
+pub const ROLL_DURATION_SECS: f32 = 0.3;
+pub const ROLL_COOLDOWN_SECS: f32 = 1.5;
+/// Chance a player shot is a critical hit, and how much extra it deals.
+pub const PLAYER_CRIT_CHANCE: f64 = 0.1;
+pub const PLAYER_CRIT_MULTIPLIER: f32 = 1.5;
+/// Points for each enemy killed.
+pub const KILL_SCORE: u32 = 100;
+/// Bosses are their biome's toughest enemy with this much more health.
+pub const BOSS_HEALTH_MULTIPLIER: f32 = 8.0;
+pub const BOSS_SCALE: f32 = 1.8;
+/// Distance at which a locked-on player stops walking and starts auto-attacking.
+pub const PLAYER_ATTACK_RANGE: f32 = 20.0;
+/// Distance at which enemies open fire on the player.
+pub const ENEMY_ATTACK_RANGE: f32 = 35.0;
+/// Radius around the player that attack-move scans for something to engage.
+pub const ATTACK_MOVE_ACQUIRE_RADIUS: f32 = 25.0;
+/// Radius of the pick volume around an enemy's torso for cursor targeting.
+pub const ENEMY_PICK_RADIUS: f32 = 1.5;
+/// Radius of the cursor circle shown while attack-move is armed.
+pub const ATTACK_MOVE_CURSOR_RADIUS: f32 = 2.0;
+/// How far the wall trails behind the furthest point the player has reached.
+pub const WALL_TRAIL: f32 = 15.0;
+/// The HUD wall readout turns red inside this distance.
+pub const WALL_WARNING_DISTANCE: f32 = 5.0;
 
 #[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
 pub enum GameState {
@@ -13,6 +74,18 @@ pub enum GameState {
     Playing,
     GameOver,
     Victory,
+    /// Passed through for a frame so a restart leaves and re-enters `Playing`.
+    Restarting,
+}
+
+/// Whether a run in progress is being simulated. Paused keeps the world
+/// around but stops every gameplay system and virtual time.
+#[derive(SubStates, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+#[source(GameState = GameState::Playing)]
+pub enum PlayState {
+    #[default]
+    Running,
+    Paused,
 }
 
 #[derive(Component)]
@@ -47,6 +120,10 @@ pub struct ScoreUI;
 #[derive(Component)]
 pub struct LivesUI;
 
+/// Seed for everything procedural in a run, rolled when the run starts.
+#[derive(Resource, Default)]
+pub struct RunSeed(pub u64);
+
 #[derive(Resource)]
 pub struct PlayerChoice {
     pub character_path: String,
@@ -58,10 +135,97 @@ pub struct Player;
 #[derive(Component)]
 pub struct Enemy;
 
+/// Enemy guarding the start of a biome section.
+#[derive(Component)]
+pub struct Boss;
+
+/// Broad role of an enemy, so they can be told apart at a glance.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnemyArchetype {
+    Scout,
+    Grunt,
+    Brute,
+}
+
+impl EnemyArchetype {
+    pub fn color(self) -> Color {
+        match self {
+            EnemyArchetype::Scout => Color::srgb(1.0, 0.85, 0.2),
+            EnemyArchetype::Grunt => Color::srgb(1.0, 0.25, 0.2),
+            EnemyArchetype::Brute => Color::srgb(0.8, 0.3, 1.0),
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct Health {
     pub current: f32,
     pub max: f32,
+    /// Soaks up damage before health does.
+    pub shield: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max, shield: 0.0 }
+    }
+
+    /// Applies `amount` damage, shield first; true when this is the hit that brings it down.
+    pub fn take(&mut self, amount: f32) -> bool {
+        let was_alive = self.current > 0.0;
+        let absorbed = amount.min(self.shield);
+        self.shield -= absorbed;
+        self.current -= amount - absorbed;
+        was_alive && self.current <= 0.0
+    }
+}
+
+/// Enemy level, one per biome section the run has reached. Shown on the
+/// health bar only; it doesn't change stats.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Level(pub u32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DamageKind {
+    Hit,
+    Crit,
+    /// Ticks from lingering sources like storms, smoke and puddles.
+    OverTime,
+}
+
+/// Sent whenever a player or enemy takes damage.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: f32,
+    pub kind: DamageKind,
+    /// Where the target was when hit.
+    pub position: Vec3,
+    /// Direction the hit travelled in; zero for area and damage-over-time sources.
+    pub direction: Vec3,
+    /// This hit brought the target down.
+    pub fatal: bool,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct HealEvent {
+    pub target: Entity,
+    pub amount: f32,
+}
+
+/// Points awarded, and where in the world they were earned.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ScoreEvent {
+    pub position: Vec3,
+    pub points: u32,
+}
+
+/// A character fired a shot from `position` along `direction`.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ShotEvent {
+    pub shooter: Entity,
+    pub position: Vec3,
+    pub direction: Vec3,
 }
 
 #[derive(Component)]
@@ -70,19 +234,150 @@ pub struct AttackTimer(pub Timer);
 #[derive(Component)]
 pub struct TargetPosition(pub Vec3);
 
+/// Present on the player between death and respawn, or the end of the run on
+/// the last life. The model plays its death animation and is then hidden; all
+/// input, movement and damage are ignored until the timer runs out.
+#[derive(Component)]
+pub struct Respawning(pub Timer);
+
+/// A killed enemy playing its death animation before it is despawned. It is
+/// no longer an `Enemy`, so nothing targets it or collides with it.
+#[derive(Component)]
+pub struct Dying(pub Timer);
+
+/// Post-respawn grace period. The player flashes and cannot be hit.
+#[derive(Component)]
+pub struct Invulnerable {
+    pub timer: Timer,
+    pub flash: Timer,
+}
+
+#[derive(Component)]
+pub struct RespawnUI;
+
+#[derive(Component)]
+pub struct RespawnCountdownUI;
+
+/// Cooldowns for the player's evasive moves: the long blink toward the cursor
+/// (F) and the short dodge roll in the movement direction (E).
+#[derive(Component)]
+pub struct DashCooldowns {
+    pub dash: Timer,
+    pub roll: Timer,
+}
+
+/// Active dash or roll. Overrides normal movement and grants i-frames.
+#[derive(Component)]
+pub struct Dashing {
+    pub velocity: Vec3,
+    pub timer: Timer,
+}
+
+#[derive(Component)]
+pub struct DashUI;
+
+/// Enemy the player is locked onto. The player walks into range and then
+/// auto-attacks it until it dies or the order is replaced.
+#[derive(Component)]
+pub struct AttackTarget(pub Entity);
+
+/// Attack-move order: walk to the point, engaging the nearest enemy on the way.
+#[derive(Component)]
+pub struct AttackMove(pub Vec3);
+
+/// Ring drawn under the enemy currently under the cursor.
+#[derive(Component)]
+pub struct HoverOutline;
+
+/// Ring drawn under the player's locked target.
+#[derive(Component)]
+pub struct TargetMarker;
+
+/// Set by the attack-move key; the next fire click issues an attack-move.
+#[derive(Resource, Default)]
+pub struct AttackMoveArmed {
+    pub armed: bool,
+    /// The fire press that issued an attack-move is still held, so it
+    /// doesn't also shoot.
+    pub issuing: bool,
+}
+
+/// An order the player can queue with shift.
+#[derive(Clone, Copy, Debug)]
+pub enum PlayerCommand {
+    Move(Vec3),
+    Attack(Entity),
+    AttackMove(Vec3),
+    Dash(Vec3),
+}
+
+/// Orders waiting behind the current one, executed front to back.
+#[derive(Component, Default)]
+pub struct CommandQueue(pub VecDeque<PlayerCommand>);
+
+/// Hold position (H): never walk, only attack enemies already in range.
+#[derive(Component)]
+pub struct HoldPosition;
+
+/// Marker for a queued waypoint, drawn in the `ClickIndicator` style. Markers
+/// for queued attacks follow the enemy.
+#[derive(Component)]
+pub struct WaypointMarker(Option<Entity>);
+
+/// Ground decals showing ranges. The player's attack range is toggled with C.
 #[derive(Component)]
-pub struct HealthBar;
+pub enum RangeIndicator {
+    AttackRange,
+    Skillshot,
+    CursorArea,
+}
+
+#[derive(Resource, Default)]
+pub struct RangeIndicatorSettings {
+    pub show_attack_range: bool,
+}
+
+/// Debug overlays (F3), e.g. enemy aggro rings.
+#[derive(Resource, Default)]
+pub struct DebugMode(pub bool);
+
+/// Aim preview for the ability currently being held.
+#[derive(Resource, Default)]
+pub struct AimPreview {
+    /// Skillshot length, drawn from the player toward the cursor.
+    pub line: Option<f32>,
+    /// Area radius, drawn at the cursor.
+    pub area: Option<f32>,
+}
+
+#[derive(Resource)]
+pub struct WaypointAssets {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+}
 
 #[derive(Component)]
 pub struct Projectile {
     pub velocity: Vec3,
     pub damage: f32,
     pub is_player: bool,
+    pub crit: bool,
+}
+
+impl Projectile {
+    pub fn kind(&self) -> DamageKind {
+        if self.crit { DamageKind::Crit } else { DamageKind::Hit }
+    }
 }
 
 #[derive(Component)]
 pub struct Prop;
 
+/// The ground plane. It is large enough to never need moving, so the
+/// floating-origin rebase leaves it alone.
+#[derive(Component)]
+pub struct Ground;
+
 #[derive(Component)]
 pub struct ClickIndicator;
 
@@ -93,10 +388,101 @@ pub struct MenuUI;
 pub enum MenuButton {
     SelectCharA,
     SelectCharB,
+    WallMode,
+    Settings,
     StartGame,
     Restart,
 }
 
+#[derive(Component)]
+pub struct WallModeLabel;
+
+#[derive(Component)]
+pub struct WallUI;
+
+/// How the wall behind the player moves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WallMode {
+    /// Trails `WALL_TRAIL` behind the player's furthest progress.
+    Follow,
+    /// Also creeps forward on its own at `speed` units per second.
+    Advance { speed: f32 },
+}
+
+impl WallMode {
+    pub fn next(self) -> Self {
+        match self {
+            WallMode::Follow => WallMode::Advance { speed: 1.5 },
+            WallMode::Advance { speed } if speed < 3.0 => WallMode::Advance { speed: 3.0 },
+            WallMode::Advance { .. } => WallMode::Follow,
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            WallMode::Follow => "Wall: Follows you".to_string(),
+            WallMode::Advance { speed } => format!("Wall: Advances {:.1} m/s", speed),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct WallSettings {
+    pub mode: WallMode,
+}
+
+impl Default for WallSettings {
+    fn default() -> Self {
+        Self { mode: WallMode::Follow }
+    }
+}
+
+/// Which inputs move the player.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ControlScheme {
+    /// Clicking Move and the movement keys both move.
+    #[default]
+    Hybrid,
+    /// Clicking Move moves; the movement keys do nothing.
+    ClickToMove,
+    /// The movement keys move; clicking Move only picks targets.
+    Keyboard,
+}
+
+impl ControlScheme {
+    pub fn next(self) -> Self {
+        match self {
+            ControlScheme::Hybrid => ControlScheme::ClickToMove,
+            ControlScheme::ClickToMove => ControlScheme::Keyboard,
+            ControlScheme::Keyboard => ControlScheme::Hybrid,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ControlScheme::Hybrid => "Mouse + keyboard",
+            ControlScheme::ClickToMove => "Click to move",
+            ControlScheme::Keyboard => "WASD",
+        }
+    }
+
+    pub fn click_moves(self) -> bool {
+        self != ControlScheme::Keyboard
+    }
+
+    pub fn keys_move(self) -> bool {
+        self != ControlScheme::ClickToMove
+    }
+}
+
+/// How abilities are cast.
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AbilitySettings {
+    /// Blink on releasing its key, aiming while held, rather than on press.
+    pub blink_on_release: bool,
+}
+
 #[derive(Resource)]
 pub struct EnemySpawnTimer(pub Timer);
 
@@ -104,12 +490,16 @@ pub struct EnemySpawnTimer(pub Timer);
 pub struct Progress {
     pub min_x: f32,
     pub wall_x: f32,
+    /// Biome section whose boss is still to come. The opening section has none.
+    pub next_boss_section: i32,
 }
 
 #[derive(Resource, Default)]
 pub struct HoverPosition {
     pub cursor: Option<Vec2>,
     pub world: Vec3,
+    /// Enemy under the cursor, picked by casting the cursor ray.
+    pub enemy: Option<Entity>,
 }
 
 impl Default for Progress {
@@ -117,6 +507,7 @@ impl Default for Progress {
         Self {
             min_x: 0.0,
             wall_x: 20.0,
+            next_boss_section: 1,
         }
     }
 }
@@ -127,16 +518,30 @@ impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app
             .init_state::<GameState>()
+            .add_event::<DamageEvent>()
+            .add_event::<HealEvent>()
+            .add_event::<ScoreEvent>()
+            .add_event::<ShotEvent>()
             .insert_resource(PlayerChoice { character_path: "Models/GLB_format/character-a.glb".to_string() })
             .insert_resource(EnemySpawnTimer(Timer::from_seconds(5.0, TimerMode::Repeating)))
             .init_resource::<Progress>()
             .init_resource::<HoverPosition>()
+            .init_resource::<AttackMoveArmed>()
+            .init_resource::<RangeIndicatorSettings>()
+            .init_resource::<DebugMode>()
+            .init_resource::<AimPreview>()
             .init_resource::<Score>()
             .init_resource::<LoadingAssets>()
+            .init_resource::<RunSeed>()
+            .init_resource::<WallSettings>()
+            .init_resource::<ControlScheme>()
+            .init_resource::<AbilitySettings>()
             .insert_resource(PlayerLives(3))
             .add_systems(OnEnter(GameState::Loading), setup_loading)
             .add_systems(Update, check_loading.run_if(in_state(GameState::Loading)))
             .add_systems(OnExit(GameState::Loading), cleanup_loading)
+            .add_sub_state::<PlayState>()
+            .add_systems(OnEnter(GameState::Restarting), restart_run)
             .add_systems(OnEnter(GameState::Menu), setup_menu)
             .add_systems(Update, menu_interaction.run_if(in_state(GameState::Menu).or(in_state(GameState::GameOver)).or(in_state(GameState::Victory))))
             .add_systems(OnExit(GameState::Menu), cleanup_menu)
@@ -144,30 +549,45 @@ impl Plugin for GamePlugin {
             .add_systems(Update, (
                 handle_input,
                 move_player,
-                move_camera,
                 update_hover_position,
                 player_aiming,
                 spawn_enemies,
+                spawn_bosses,
                 move_enemies,
-                combat_system,
+                combat_system.after(handle_input),
                 update_projectiles,
-                update_health_bars,
                 handle_death,
-                loop_environment,
                 update_ui,
-            ).run_if(in_state(GameState::Playing)))
+            ).run_if(in_state(PlayState::Running)))
+            .add_systems(Update, (
+                respawn_player,
+                update_invulnerability,
+                handle_dash,
+                update_dash_ui,
+                update_attack_orders,
+                update_target_markers,
+                advance_command_queue,
+                update_waypoint_markers,
+                toggle_range_indicators,
+                update_range_indicators,
+                draw_enemy_aggro_ranges,
+                advance_wall,
+                update_wall_ui,
+            ).run_if(in_state(PlayState::Running)))
             .add_systems(Update, update_menu_highlights.run_if(in_state(GameState::Menu)))
             .add_systems(OnExit(GameState::Playing), cleanup_playing)
             .add_systems(OnEnter(GameState::GameOver), setup_game_over)
             .add_systems(OnExit(GameState::GameOver), cleanup_menu)
             .add_systems(OnEnter(GameState::Victory), setup_victory)
-            .add_systems(OnExit(GameState::Victory), cleanup_menu);
+            .add_systems(OnExit(GameState::Victory), cleanup_menu)
+            .add_plugins((LanePlugin, CameraRigPlugin, BiomePlugin, PropsPlugin, HazardsPlugin, DayNightPlugin, WeatherPlugin, VisionPlugin))
+            .add_plugins((MinimapPlugin, GameFeelPlugin, FloatingNumbersPlugin, HealthBarPlugin, CharacterAnimationPlugin, ParticlesPlugin, GameAudioPlugin, PausePlugin, GraphicsPlugin, SettingsPlugin, InputMapPlugin));
     }
 }
 
 fn setup_loading(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.spawn((
-        Camera2d::default(),
+        Camera2d,
         LoadingUI,
     ));
 
@@ -213,39 +633,43 @@ fn setup_loading(mut commands: Commands, asset_server: Res<AssetServer>) {
     });
 
     // Start loading all required assets to cache them
-    let mut handles = Vec::new();
-
-    // Characters
-    handles.push(asset_server.load_untyped("Models/GLB_format/character-a.glb").into());
-    handles.push(asset_server.load_untyped("Models/GLB_format/character-b.glb").into());
-
-    // Previews
-    handles.push(asset_server.load_untyped("Previews/character-a.png").into());
-    handles.push(asset_server.load_untyped("Previews/character-b.png").into());
-
-    // Buildings
-    for path in [
-        "Models/GLB_format/building-i.glb",
-        "Models/GLB_format/building-p.glb",
-        "Models/GLB_format/building-j.glb",
-        "Models/GLB_format/building-s.glb",
-    ] {
-        handles.push(asset_server.load_untyped(path).into());
+    let mut handles: Vec<UntypedHandle> = vec![
+        // Characters
+        asset_server.load_untyped("Models/GLB_format/character-a.glb").into(),
+        asset_server.load_untyped("Models/GLB_format/character-b.glb").into(),
+        // Previews
+        asset_server.load_untyped("Previews/character-a.png").into(),
+        asset_server.load_untyped("Previews/character-b.png").into(),
+    ];
+
+    // Lane: buildings, road tiles and props
+    for path in BUILDING_MODELS.iter().chain(LANE_MODELS.iter()) {
+        handles.push(asset_server.load_untyped(*path).into());
     }
 
-    // Enemies
-    for path in [
-        "Models/GLB_format/character-p.glb",
-        "Models/GLB_format/character-q.glb",
-        "Models/GLB_format/character-n.glb",
-        "Models/GLB_format/character-m.glb",
-    ] {
+    // Biomes: ground textures and enemy models
+    for path in biome_asset_paths() {
         handles.push(asset_server.load_untyped(path).into());
     }
 
     // Terrain
     handles.push(asset_server.load_untyped("PNG/Default/terrain_sand_top_a.png").into());
 
+    // HUD digits
+    for path in number_glyph_paths() {
+        handles.push(asset_server.load_untyped(path).into());
+    }
+
+    // Particle effect definitions
+    for path in PARTICLE_EFFECT_PATHS {
+        handles.push(asset_server.load_untyped(path).into());
+    }
+
+    // Music and sound effects
+    for path in audio_asset_paths() {
+        handles.push(asset_server.load_untyped(path).into());
+    }
+
     commands.insert_resource(LoadingAssets { handles });
 }
 
@@ -287,9 +711,9 @@ fn cleanup_loading(mut commands: Commands, query: Query<Entity, With<LoadingUI>>
     }
 }
 
-fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>, wall_settings: Res<WallSettings>) {
     commands.spawn((
-        Camera2d::default(),
+        Camera2d,
         MenuUI,
     ));
 
@@ -336,7 +760,7 @@ fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                     align_items: AlignItems::Center,
                     ..default()
                 },
-                BackgroundColor(Color::srgb(0.15, 0.15, 0.15).into()),
+                BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
                 MenuButton::SelectCharA,
             )).with_children(|p| {
                 p.spawn((
@@ -362,7 +786,7 @@ fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                     align_items: AlignItems::Center,
                     ..default()
                 },
-                BackgroundColor(Color::srgb(0.15, 0.15, 0.15).into()),
+                BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
                 MenuButton::SelectCharB,
             )).with_children(|p| {
                 p.spawn((
@@ -377,6 +801,36 @@ fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
             });
         });
 
+        // Wall mode
+        parent.spawn((
+            Button,
+            Node {
+                width: Val::Px(320.0),
+                height: Val::Px(45.0),
+                margin: UiRect::top(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+            MenuButton::WallMode,
+        )).with_child((Text::new(wall_settings.mode.label()), TextFont::from_font_size(22.0), WallModeLabel));
+
+        // Settings
+        parent.spawn((
+            Button,
+            Node {
+                width: Val::Px(320.0),
+                height: Val::Px(45.0),
+                margin: UiRect::top(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+            MenuButton::Settings,
+        )).with_child((Text::new("Settings"), TextFont::from_font_size(22.0)));
+
         // Start Game
         parent.spawn((
             Button,
@@ -388,15 +842,20 @@ fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                 align_items: AlignItems::Center,
                 ..default()
             },
-            BackgroundColor(Color::srgb(0.15, 0.45, 0.15).into()),
+            BackgroundColor(Color::srgb(0.15, 0.45, 0.15)),
             MenuButton::StartGame,
         )).with_child((Text::new("START GAME"), TextFont::from_font_size(30.0)));
     });
 }
 
+#[allow(clippy::type_complexity)]
 fn menu_interaction(
+    mut commands: Commands,
     mut next_state: ResMut<NextState<GameState>>,
     mut player_choice: ResMut<PlayerChoice>,
+    mut wall_settings: ResMut<WallSettings>,
+    mut settings_menu: ResMut<SettingsMenu>,
+    mut label_query: Query<&mut Text, With<WallModeLabel>>,
     interaction_query: Query<
         (&Interaction, &MenuButton),
         (Changed<Interaction>, With<Button>),
@@ -411,7 +870,17 @@ fn menu_interaction(
                 MenuButton::SelectCharB => {
                     player_choice.character_path = "Models/GLB_format/character-b.glb".to_string();
                 }
+                MenuButton::WallMode => {
+                    wall_settings.mode = wall_settings.mode.next();
+                    for mut text in &mut label_query {
+                        text.0 = wall_settings.mode.label();
+                    }
+                }
+                MenuButton::Settings => {
+                    settings_menu.open = true;
+                }
                 MenuButton::StartGame => {
+                    start_run(&mut commands);
                     next_state.set(GameState::Playing);
                 }
                 MenuButton::Restart => {
@@ -422,6 +891,17 @@ fn menu_interaction(
     }
 }
 
+/// Fresh lane and progress for a new run.
+fn start_run(commands: &mut Commands) {
+    commands.insert_resource(RunSeed(rand::thread_rng().gen()));
+    commands.insert_resource(Progress::default());
+}
+
+fn restart_run(mut commands: Commands, mut next_state: ResMut<NextState<GameState>>) {
+    start_run(&mut commands);
+    next_state.set(GameState::Playing);
+}
+
 fn update_menu_highlights(
     player_choice: Res<PlayerChoice>,
     mut button_query: Query<(&MenuButton, &mut BackgroundColor), With<Button>>,
@@ -464,6 +944,7 @@ fn setup_game(
     commands.spawn((
         Camera3d::default(),
         Transform::from_xyz(20.0, 20.0, 20.0).looking_at(Vec3::ZERO, Vec3::Y),
+        default_fog(),
     ));
 
     // Light
@@ -487,13 +968,27 @@ fn setup_game(
     // Character
     commands.spawn((
         Player,
-        Health { current: 200.0, max: 200.0 },
+        Health::new(200.0),
+        CharacterAnimator::new(&player_choice.character_path),
         AttackTimer({
             let mut t = Timer::from_seconds(0.5, TimerMode::Once);
             t.set_elapsed(std::time::Duration::from_secs_f32(0.5));
             t
         }),
         TargetPosition(Vec3::ZERO),
+        CommandQueue::default(),
+        DashCooldowns {
+            dash: {
+                let mut t = Timer::from_seconds(DASH_COOLDOWN_SECS, TimerMode::Once);
+                t.set_elapsed(std::time::Duration::from_secs_f32(DASH_COOLDOWN_SECS));
+                t
+            },
+            roll: {
+                let mut t = Timer::from_seconds(ROLL_COOLDOWN_SECS, TimerMode::Once);
+                t.set_elapsed(std::time::Duration::from_secs_f32(ROLL_COOLDOWN_SECS));
+                t
+            },
+        },
         Transform::from_xyz(0.0, 0.0, 0.0),
         Visibility::Visible,
         InheritedVisibility::default(),
@@ -505,34 +1000,6 @@ fn setup_game(
             Visibility::Visible,
             InheritedVisibility::default(),
         ));
-
-        // Health bar background
-        parent.spawn((
-            Mesh3d(meshes.add(Plane3d::default().mesh().size(2.0, 0.2))),
-            MeshMaterial3d(materials.add(StandardMaterial {
-                base_color: Color::srgb(0.2, 0.0, 0.0),
-                unlit: true,
-                ..default()
-            })),
-            Transform::from_xyz(0.0, 3.5, 0.0).with_rotation(Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
-            Visibility::Visible,
-            InheritedVisibility::default(),
-        ));
-        // Health bar foreground
-        parent.spawn((
-            HealthBar,
-            Mesh3d(meshes.add(Plane3d::default().mesh().size(1.0, 1.0))),
-            MeshMaterial3d(materials.add(StandardMaterial {
-                base_color: Color::srgb(0.0, 1.0, 0.0),
-                unlit: true,
-                ..default()
-            })),
-            Transform::from_xyz(0.0, 3.51, 0.0)
-                .with_rotation(Quat::from_rotation_x(std::f32::consts::FRAC_PI_2))
-                .with_scale(Vec3::new(2.0, 1.0, 0.2)),
-            Visibility::Visible,
-            InheritedVisibility::default(),
-        ));
     });
 
     // Ground plane
@@ -545,6 +1012,7 @@ fn setup_game(
     }
 
     commands.spawn((
+        Ground,
         Mesh3d(meshes.add(grass_mesh)),
         MeshMaterial3d(materials.add(StandardMaterial {
             base_color: Color::srgb(0.5, 0.7, 0.5),
@@ -567,99 +1035,84 @@ fn setup_game(
     ));
 
     // Click Indicator (Small dot)
+    let indicator_mesh = meshes.add(Sphere::new(0.2).mesh());
+    let indicator_material = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.0, 1.0, 1.0), // Cyan
+        emissive: LinearRgba::new(0.0, 2.0, 2.0, 1.0),
+        ..default()
+    });
     commands.spawn((
         ClickIndicator,
-        Mesh3d(meshes.add(Sphere::new(0.2).mesh())),
-        MeshMaterial3d(materials.add(StandardMaterial {
-            base_color: Color::srgb(0.0, 1.0, 1.0), // Cyan
-            emissive: LinearRgba::new(0.0, 2.0, 2.0, 1.0),
-            ..default()
-        })),
+        Mesh3d(indicator_mesh.clone()),
+        MeshMaterial3d(indicator_material.clone()),
         Transform::from_xyz(0.0, -1.0, 0.0),
         Visibility::Visible,
         InheritedVisibility::default(),
     ));
-
-    // Spawn Buildings in a left-diagonal lane layout (x axis)
-    let requested_buildings = [
-        "Models/GLB_format/building-i.glb",
-        "Models/GLB_format/building-p.glb",
-        "Models/GLB_format/building-j.glb",
-        "Models/GLB_format/building-s.glb",
-    ];
-
-    let spacing = 10.0; // Increased density
-    let building_scale = 2.5;
-
-    // Tree assets (primitives)
-    let trunk_mesh = meshes.add(Cylinder::new(0.3, 1.0));
-    let leaves_mesh = meshes.add(Cone::new(1.2, 2.5));
-    let trunk_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.4, 0.2, 0.1),
-        ..default()
-    });
-    let leaves_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.1, 0.5, 0.1),
-        ..default()
+    commands.insert_resource(WaypointAssets {
+        mesh: indicator_mesh,
+        material: indicator_material,
     });
 
-    for i in -40i32..=40 {
-        let t = i as f32 * spacing;
-        let center = Vec3::new(t, 0.0, 0.0);
-        let side_offset = Vec3::Z * LANE_HALF_WIDTH;
-
-        // Buildings
-        commands.spawn((
-            Prop,
-            SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset(requested_buildings[(i.abs() as usize) % 4]))),
-            Transform::from_translation(center + side_offset)
-                .with_scale(Vec3::splat(building_scale)),
-            Visibility::Visible,
-            InheritedVisibility::default(),
-        ));
-
-        commands.spawn((
-            Prop,
-            SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset(requested_buildings[((i.abs() as usize) + 2) % 4]))),
-            Transform::from_translation(center - side_offset)
-                .with_scale(Vec3::splat(building_scale)),
-            Visibility::Visible,
-            InheritedVisibility::default(),
-        ));
-
-        // Trees between buildings
-        if i < 40 {
-            let tree_t = t + spacing / 2.0;
-            let tree_center = Vec3::new(tree_t, 0.0, 0.0);
-            
-            spawn_tree(&mut commands, tree_center + side_offset, trunk_mesh.clone(), leaves_mesh.clone(), trunk_material.clone(), leaves_material.clone());
-            spawn_tree(&mut commands, tree_center - side_offset, trunk_mesh.clone(), leaves_mesh.clone(), trunk_material.clone(), leaves_material.clone());
-        }
-    }
-}
+    // Range decals
+    let decal_material = |color: Color| StandardMaterial {
+        base_color: color,
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    };
+    commands.spawn((
+        RangeIndicator::AttackRange,
+        Mesh3d(meshes.add(Annulus::new(PLAYER_ATTACK_RANGE - 0.15, PLAYER_ATTACK_RANGE).mesh().resolution(96))),
+        MeshMaterial3d(materials.add(decal_material(Color::srgba(0.3, 0.8, 1.0, 0.6)))),
+        Transform::from_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
+        Visibility::Hidden,
+        InheritedVisibility::default(),
+    ));
+    commands.spawn((
+        RangeIndicator::Skillshot,
+        Mesh3d(meshes.add(Plane3d::default().mesh().size(1.0, 1.0))),
+        MeshMaterial3d(materials.add(decal_material(Color::srgba(0.2, 0.6, 1.0, 0.35)))),
+        Transform::default(),
+        Visibility::Hidden,
+        InheritedVisibility::default(),
+    ));
+    commands.spawn((
+        RangeIndicator::CursorArea,
+        Mesh3d(meshes.add(Circle::new(1.0).mesh().resolution(48))),
+        MeshMaterial3d(materials.add(decal_material(Color::srgba(1.0, 0.3, 0.2, 0.3)))),
+        Transform::from_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
+        Visibility::Hidden,
+        InheritedVisibility::default(),
+    ));
 
-fn spawn_tree(
-    commands: &mut Commands,
-    pos: Vec3,
-    trunk_mesh: Handle<Mesh>,
-    leaves_mesh: Handle<Mesh>,
-    trunk_mat: Handle<StandardMaterial>,
-    leaves_mat: Handle<StandardMaterial>,
-) {
+    // Hover outline and lock-on marker (flat rings under enemies)
+    let ring_mesh = meshes.add(Annulus::new(1.1, 1.35).mesh());
     commands.spawn((
-        Prop,
-        Mesh3d(trunk_mesh),
-        MeshMaterial3d(trunk_mat),
-        Transform::from_translation(pos + Vec3::Y * 0.5),
-        Visibility::Visible,
+        HoverOutline,
+        Mesh3d(ring_mesh.clone()),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::srgb(1.0, 0.9, 0.2),
+            emissive: LinearRgba::new(2.0, 1.8, 0.4, 1.0),
+            unlit: true,
+            ..default()
+        })),
+        Transform::from_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
+        Visibility::Hidden,
         InheritedVisibility::default(),
     ));
     commands.spawn((
-        Prop,
-        Mesh3d(leaves_mesh),
-        MeshMaterial3d(leaves_mat),
-        Transform::from_translation(pos + Vec3::Y * 2.0),
-        Visibility::Visible,
+        TargetMarker,
+        Mesh3d(ring_mesh),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::srgb(1.0, 0.1, 0.1),
+            emissive: LinearRgba::new(3.0, 0.2, 0.2, 1.0),
+            unlit: true,
+            ..default()
+        })),
+        Transform::from_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2))
+            .with_scale(Vec3::splat(1.2)),
+        Visibility::Hidden,
         InheritedVisibility::default(),
     ));
 }
@@ -669,111 +1122,134 @@ fn spawn_enemies(
     asset_server: Res<AssetServer>,
     time: Res<Time>,
     mut timer: ResMut<EnemySpawnTimer>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
     player_query: Query<&Transform, With<Player>>,
+    time_of_day: Res<TimeOfDay>,
+    layout: Res<LaneLayout>,
 ) {
     if timer.0.tick(time.delta()).just_finished() {
         if let Ok(player_transform) = player_query.get_single() {
             let mut rng = rand::thread_rng();
-            
-            let enemy_models = [
-                "Models/GLB_format/character-p.glb",
-                "Models/GLB_format/character-q.glb",
-                "Models/GLB_format/character-n.glb",
-                "Models/GLB_format/character-m.glb",
-            ];
-            let model_path = enemy_models[rng.gen_range(0..enemy_models.len())];
-
-            // Spawn ahead of player
+
+            // Spawn ahead of player, as the biome there
             let spawn_x = player_transform.translation.x - 60.0;
             let spawn_z = (time.elapsed_secs().sin() * 5.0) + rng.gen_range(-3.0..3.0);
-            
-            commands.spawn((
-                Enemy,
-                Health { current: 100.0, max: 100.0 },
-                AttackTimer({
-                    let mut t = Timer::from_seconds(2.0, TimerMode::Once);
-                    t.set_elapsed(std::time::Duration::from_secs_f32(2.0));
-                    t
-                }),
-                Transform::from_xyz(spawn_x, 0.0, spawn_z),
-                Visibility::Visible,
-                InheritedVisibility::default(),
-            )).with_children(|parent| {
-                // Enemy Model (rotated 180 degrees)
-                parent.spawn((
-                    SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset(model_path))),
-                    Transform::from_rotation(Quat::from_rotation_y(std::f32::consts::PI)),
-                    Visibility::Visible,
-                    InheritedVisibility::default(),
-                ));
+            let spawn_index = layout.index_at(spawn_x);
 
-                // Health bar background
-                parent.spawn((
-                    Mesh3d(meshes.add(Plane3d::default().mesh().size(2.0, 0.2))),
-                    MeshMaterial3d(materials.add(StandardMaterial {
-                        base_color: Color::srgb(0.2, 0.0, 0.0),
-                        unlit: true,
-                        ..default()
-                    })),
-                    Transform::from_xyz(0.0, 3.5, 0.0).with_rotation(Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
-                    Visibility::Visible,
-                    InheritedVisibility::default(),
-                ));
-                // Health bar foreground
-                parent.spawn((
-                    HealthBar,
-                    Mesh3d(meshes.add(Plane3d::default().mesh().size(1.0, 1.0))),
-                    MeshMaterial3d(materials.add(StandardMaterial {
-                        base_color: Color::srgb(1.0, 0.0, 0.0), // Red for enemies
-                        unlit: true,
-                        ..default()
-                    })),
-                    Transform::from_xyz(0.0, 3.51, 0.0)
-                        .with_rotation(Quat::from_rotation_x(std::f32::consts::FRAC_PI_2))
-                        .with_scale(Vec3::new(2.0, 1.0, 0.2)),
-                    Visibility::Visible,
-                    InheritedVisibility::default(),
-                ));
-            });
-        }
-    }
-}
+            let spawn_entry = Biome::for_segment(layout.generator.seed, spawn_index).def().pick_enemy(&mut rng);
 
-fn move_enemies(
-    time: Res<Time>,
-    player_query: Query<&Transform, (With<Player>, Without<Enemy>)>,
-    mut enemy_query: Query<&mut Transform, (With<Enemy>, Without<Player>)>,
-) {
-    if let Ok(player_transform) = player_query.get_single() {
-        for mut enemy_transform in &mut enemy_query {
-            let player_pos = player_transform.translation;
-            let enemy_pos = enemy_transform.translation;
-            let dir = (player_pos - enemy_pos).normalize();
-            let dist = player_pos.distance(enemy_pos);
-            
-            if dist > 10.0 {
-                enemy_transform.translation += dir * 5.0 * time.delta_secs();
-                enemy_transform.look_to(dir, Vec3::Y);
+            // Night waves are bigger and tougher
+            let (health, scale) = if time_of_day.is_night() {
+                (spawn_entry.health * NIGHT_ENEMY_HEALTH_MULTIPLIER, NIGHT_ENEMY_SCALE)
             } else {
-                // Just face the player if close enough to stop
-                enemy_transform.look_to(dir, Vec3::Y);
-            }
+                (spawn_entry.health, 1.0)
+            };
+
+            let level = Biome::section(spawn_index).max(0) as u32 + 1;
+
+            spawn_enemy(&mut commands, &asset_server, spawn_entry, Vec3::new(spawn_x, 0.0, spawn_z), health, scale, level);
         }
     }
 }
 
-fn combat_system(
+/// Spawns each section's boss as its first segment comes into spawn range.
+fn spawn_bosses(
     mut commands: Commands,
-    time: Res<Time>,
-    keys: Res<ButtonInput<KeyCode>>,
-    mouse_button_input: Res<ButtonInput<MouseButton>>,
-    mut player_query: Query<(&Transform, &mut AttackTimer), With<Player>>,
-    mut enemy_query: Query<(&Transform, &mut AttackTimer), (With<Enemy>, Without<Player>)>,
+    asset_server: Res<AssetServer>,
+    mut progress: ResMut<Progress>,
+    player_query: Query<&Transform, (With<Player>, Without<Respawning>)>,
+    layout: Res<LaneLayout>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let index = progress.next_boss_section * BIOME_SECTION_SEGMENTS;
+    if layout.index_at(player_transform.translation.x - 60.0) < index {
+        return;
+    }
+
+    let entry = Biome::for_segment(layout.generator.seed, index).def().boss();
+    let level = progress.next_boss_section as u32 + 1;
+    let health = entry.health * BOSS_HEALTH_MULTIPLIER;
+    let boss = spawn_enemy(&mut commands, &asset_server, entry, Vec3::X * layout.segment_x(index), health, BOSS_SCALE, level);
+    commands.entity(boss).insert(Boss);
+    progress.next_boss_section += 1;
+}
+
+fn spawn_enemy(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    entry: &SpawnEntry,
+    pos: Vec3,
+    health: f32,
+    scale: f32,
+    level: u32,
+) -> Entity {
+    commands.spawn((
+        Enemy,
+        entry.archetype,
+        Health::new(health),
+        Level(level),
+        CharacterAnimator::new(entry.model),
+        AttackTimer({
+            let mut t = Timer::from_seconds(2.0, TimerMode::Once);
+            t.set_elapsed(std::time::Duration::from_secs_f32(2.0));
+            t
+        }),
+        Transform::from_translation(pos),
+        Visibility::Visible,
+        InheritedVisibility::default(),
+    )).with_children(|parent| {
+        // Enemy Model (rotated 180 degrees)
+        parent.spawn((
+            SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset(entry.model))),
+            Transform::from_rotation(Quat::from_rotation_y(std::f32::consts::PI)).with_scale(Vec3::splat(scale)),
+            Visibility::Visible,
+            InheritedVisibility::default(),
+        ));
+    }).id()
+}
+
+fn move_enemies(
+    time: Res<Time>,
+    layout: Res<LaneLayout>,
+    player_query: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    mut enemy_query: Query<&mut Transform, (With<Enemy>, Without<Player>)>,
+) {
+    if let Ok(player_transform) = player_query.get_single() {
+        for mut enemy_transform in &mut enemy_query {
+            let player_pos = player_transform.translation;
+            let enemy_pos = enemy_transform.translation;
+            let dir = (player_pos - enemy_pos).normalize();
+            let dist = player_pos.distance(enemy_pos);
+            
+            if dist > 10.0 {
+                let step_dir = layout.steer(enemy_pos, dir, 3.0);
+                let next_pos = enemy_pos + step_dir * 5.0 * time.delta_secs();
+                enemy_transform.translation = layout.resolve_collision(next_pos, CHARACTER_RADIUS);
+                enemy_transform.look_to(dir, Vec3::Y);
+            } else {
+                // Just face the player if close enough to stop
+                enemy_transform.look_to(dir, Vec3::Y);
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn combat_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    actions: Actions,
+    mut player_query: Query<(Entity, &Transform, &mut AttackTimer, Option<&AttackTarget>, Option<&Empowered>), (With<Player>, Without<Respawning>)>,
+    mut enemy_query: Query<(Entity, &Transform, &mut AttackTimer), (With<Enemy>, Without<Player>)>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     hover_pos: Res<HoverPosition>,
+    attack_move_armed: Res<AttackMoveArmed>,
+    weather: Res<CurrentWeather>,
+    layout: Res<LaneLayout>,
+    minimap_query: MinimapInteraction,
+    mut shot_events: EventWriter<ShotEvent>,
 ) {
     let projectile_mesh = meshes.add(Cuboid::new(0.1, 0.1, 1.5).mesh());
     let player_projectile_mat = materials.add(StandardMaterial {
@@ -787,11 +1263,21 @@ fn combat_system(
         ..default()
     });
 
-    if let Ok((player_transform, mut player_timer)) = player_query.get_single_mut() {
+    if let Ok((player_entity, player_transform, mut player_timer, attack_target, empowered)) = player_query.get_single_mut() {
         player_timer.0.tick(time.delta());
-        
-        if (keys.pressed(KeyCode::Space) || mouse_button_input.pressed(MouseButton::Left)) && player_timer.0.finished() {
-            let mut shoot_dir = hover_pos.world - player_transform.translation;
+
+        // A locked target in range is auto-attacked, otherwise fire toward the cursor
+        let locked_pos = attack_target
+            .and_then(|target| enemy_query.get(target.0).ok())
+            .map(|(_, enemy_transform, _)| enemy_transform.translation)
+            .filter(|pos| pos.distance(player_transform.translation) <= PLAYER_ATTACK_RANGE);
+        let aim_pos = locked_pos.or_else(|| {
+            let clicking = actions.pressed(Action::Fire) && !attack_move_armed.issuing && !over_minimap(&minimap_query);
+            clicking.then_some(hover_pos.world)
+        });
+
+        if let (Some(aim_pos), true) = (aim_pos, player_timer.0.finished()) {
+            let mut shoot_dir = aim_pos - player_transform.translation;
             shoot_dir.y = 0.0;
             let dir = shoot_dir.normalize_or_zero();
             
@@ -802,11 +1288,17 @@ fn combat_system(
                 dir
             };
 
+            let mut damage = if empowered.is_some() { 25.0 * EMPOWERED_DAMAGE_MULTIPLIER } else { 25.0 };
+            let crit = rand::thread_rng().gen_bool(PLAYER_CRIT_CHANCE);
+            if crit {
+                damage *= PLAYER_CRIT_MULTIPLIER;
+            }
             commands.spawn((
                 Projectile {
                     velocity: dir * 25.0,
-                    damage: 25.0,
+                    damage,
                     is_player: true,
+                    crit,
                 },
                 Mesh3d(projectile_mesh.clone()),
                 MeshMaterial3d(player_projectile_mat.clone()),
@@ -815,23 +1307,31 @@ fn combat_system(
                 Visibility::Visible,
                 InheritedVisibility::default(),
             ));
+            shot_events.send(ShotEvent {
+                shooter: player_entity,
+                position: player_transform.translation + Vec3::Y * 1.5,
+                direction: dir,
+            });
             
             player_timer.0.reset();
         }
     }
 
-    for (enemy_transform, mut enemy_timer) in &mut enemy_query {
+    for (enemy_entity, enemy_transform, mut enemy_timer) in &mut enemy_query {
         enemy_timer.0.tick(time.delta());
         if enemy_timer.0.finished() {
-            if let Ok((player_transform, _)) = player_query.get_single() {
+            if let Ok((_, player_transform, _, _, _)) = player_query.get_single() {
                 let dist = enemy_transform.translation.distance(player_transform.translation);
-                if dist < 35.0 {
+                // Bad weather shortens how far enemies can see, and they need a clear line of sight
+                let in_sight = !layout.sight_blocked(enemy_transform.translation, player_transform.translation);
+                if dist < ENEMY_ATTACK_RANGE * weather.0.def().aggro_scale && in_sight {
                     let dir = (player_transform.translation - enemy_transform.translation).normalize();
                     commands.spawn((
                         Projectile {
                             velocity: dir * 15.0,
                             damage: 10.0,
                             is_player: false,
+                            crit: false,
                         },
                         Mesh3d(projectile_mesh.clone()),
                         MeshMaterial3d(enemy_projectile_mat.clone()),
@@ -840,6 +1340,11 @@ fn combat_system(
                         Visibility::Visible,
                         InheritedVisibility::default(),
                     ));
+                    shot_events.send(ShotEvent {
+                        shooter: enemy_entity,
+                        position: enemy_transform.translation + Vec3::Y * 1.5,
+                        direction: dir,
+                    });
                     enemy_timer.0.reset();
                 }
             }
@@ -847,34 +1352,23 @@ fn combat_system(
     }
 }
 
-fn move_camera(
-    player_query: Query<&Transform, With<Player>>,
-    mut camera_query: Query<&mut Transform, (With<Camera3d>, Without<Player>)>,
-    progress: Res<Progress>,
-) {
-    if let Ok(player_transform) = player_query.get_single() {
-        if let Ok(mut camera_transform) = camera_query.get_single_mut() {
-            let offset = Vec3::new(20.0, 20.0, 20.0);
-            // Camera X follows progress.min_x (forward progress)
-            // Camera Z and Y follow current player position to stay centered
-            camera_transform.translation.x = progress.min_x + offset.x;
-            camera_transform.translation.y = player_transform.translation.y + offset.y;
-            camera_transform.translation.z = player_transform.translation.z + offset.z;
-            
-            camera_transform.look_at(Vec3::new(progress.min_x, player_transform.translation.y, player_transform.translation.z), Vec3::Y);
-        }
-    }
-}
-
+#[allow(clippy::type_complexity)]
 fn update_hover_position(
+    window_query: Query<&Window>,
     camera_query: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
     mut hover_pos: ResMut<HoverPosition>,
     progress: Res<Progress>,
     mut indicator_query: Query<&mut Transform, With<ClickIndicator>>,
+    enemy_query: Query<(Entity, &Transform), (With<Enemy>, Without<ClickIndicator>)>,
+    vision: Res<VisionGrid>,
 ) {
-    if let Some(cursor) = hover_pos.cursor {
+    hover_pos.enemy = None;
+
+    if let (Some(cursor), Ok(window)) = (hover_pos.cursor, window_query.get_single()) {
         if let Ok((camera, camera_transform)) = camera_query.get_single() {
-            if let Ok(ray) = camera.viewport_to_world(camera_transform, cursor) {
+            if let Ok(ray) = camera.viewport_to_world(camera_transform, window_to_viewport(camera, window, cursor)) {
+                hover_pos.enemy = pick_enemy(ray, &enemy_query, &vision);
+
                 let t = -ray.origin.y / ray.direction.y;
                 if t > 0.0 {
                     let mut ground_pos = ray.origin + ray.direction * t;
@@ -899,12 +1393,41 @@ fn update_hover_position(
     }
 }
 
+/// Closest enemy whose torso sphere is hit by the ray.
+#[allow(clippy::type_complexity)]
+fn pick_enemy(
+    ray: Ray3d,
+    enemy_query: &Query<(Entity, &Transform), (With<Enemy>, Without<ClickIndicator>)>,
+    vision: &VisionGrid,
+) -> Option<Entity> {
+    let mut best: Option<(Entity, f32)> = None;
+    for (entity, transform) in enemy_query {
+        // Enemies in the fog can't be clicked
+        if !vision.is_visible(transform.translation) {
+            continue;
+        }
+        let center = transform.translation + Vec3::Y * 1.5;
+        let t = (center - ray.origin).dot(*ray.direction);
+        if t <= 0.0 {
+            continue;
+        }
+        if ray.get_point(t).distance(center) < ENEMY_PICK_RADIUS && best.is_none_or(|(_, best_t)| t < best_t) {
+            best = Some((entity, t));
+        }
+    }
+    best.map(|(entity, _)| entity)
+}
+
 fn player_aiming(
     hover_pos: Res<HoverPosition>,
-    mut player_query: Query<&mut Transform, With<Player>>,
+    mut player_query: Query<(&mut Transform, Option<&AttackTarget>), With<Player>>,
+    enemy_query: Query<&Transform, (With<Enemy>, Without<Player>)>,
 ) {
-    for mut transform in &mut player_query {
-        let mut look_dir = hover_pos.world - transform.translation;
+    for (mut transform, attack_target) in &mut player_query {
+        let aim_pos = attack_target
+            .and_then(|target| enemy_query.get(target.0).ok())
+            .map_or(hover_pos.world, |enemy_transform| enemy_transform.translation);
+        let mut look_dir = aim_pos - transform.translation;
         look_dir.y = 0.0;
         if look_dir.length_squared() > 0.01 {
             transform.look_to(look_dir.normalize(), Vec3::Y);
@@ -912,33 +1435,62 @@ fn player_aiming(
     }
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn update_projectiles(
     mut commands: Commands,
     time: Res<Time>,
     mut projectile_query: Query<(Entity, &mut Transform, &Projectile), (Without<Player>, Without<Enemy>)>,
-    mut player_query: Query<(&Transform, &mut Health), With<Player>>,
-    mut enemy_query: Query<(&Transform, &mut Health), (With<Enemy>, Without<Player>)>,
+    mut player_query: Query<(Entity, &Transform, &mut Health), (With<Player>, Without<Respawning>, Without<Invulnerable>, Without<Dashing>)>,
+    mut enemy_query: Query<(Entity, &Transform, &mut Health), (With<Enemy>, Without<Player>)>,
+    mut prop_query: Query<(&Transform, &mut Destructible), Without<Projectile>>,
+    layout: Res<LaneLayout>,
+    weather: Res<CurrentWeather>,
+    mut damage_events: EventWriter<DamageEvent>,
 ) {
+    let weather = weather.0.def();
+
     for (projectile_entity, mut projectile_transform, projectile) in &mut projectile_query {
-        projectile_transform.translation += projectile.velocity * time.delta_secs();
+        let from = projectile_transform.translation;
+        // Wind pushes shots sideways at a steady drift, rain drags on them
+        let velocity = projectile.velocity + weather.wind;
+        projectile_transform.translation += velocity * weather.projectile_speed_scale * time.delta_secs();
+        if weather.wind != Vec3::ZERO {
+            projectile_transform.look_to(velocity, Vec3::Y);
+        }
 
         if projectile_transform.translation.length() > 500.0 {
             commands.entity(projectile_entity).despawn();
             continue;
         }
 
+        // Breakable props soak up shots from either side
+        let pos = projectile_transform.translation;
+        if let Some((_, mut destructible)) = prop_query.iter_mut().find(|(transform, destructible)| destructible.hit_by(transform, pos)) {
+            destructible.health -= projectile.damage;
+            commands.entity(projectile_entity).despawn();
+            continue;
+        }
+
+        // Anything solid stops enemy fire, so props double as cover
+        if !projectile.is_player && layout.path_blocked(from, pos) {
+            commands.entity(projectile_entity).despawn();
+            continue;
+        }
+
         if projectile.is_player {
-            for (enemy_transform, mut health) in &mut enemy_query {
+            for (enemy, enemy_transform, mut health) in &mut enemy_query {
                 if projectile_transform.translation.distance(enemy_transform.translation + Vec3::Y * 1.5) < 2.0 {
-                    health.current -= projectile.damage;
+                    let fatal = health.take(projectile.damage);
+                    damage_events.send(DamageEvent { target: enemy, amount: projectile.damage, kind: projectile.kind(), position: enemy_transform.translation, direction: projectile.velocity.normalize_or_zero(), fatal });
                     commands.entity(projectile_entity).despawn();
                     break;
                 }
             }
         } else {
-            if let Ok((player_transform, mut health)) = player_query.get_single_mut() {
+            if let Ok((player, player_transform, mut health)) = player_query.get_single_mut() {
                 if projectile_transform.translation.distance(player_transform.translation + Vec3::Y * 1.5) < 2.0 {
-                    health.current -= projectile.damage;
+                    let fatal = health.take(projectile.damage);
+                    damage_events.send(DamageEvent { target: player, amount: projectile.damage, kind: projectile.kind(), position: player_transform.translation, direction: projectile.velocity.normalize_or_zero(), fatal });
                     commands.entity(projectile_entity).despawn();
                 }
             }
@@ -946,55 +1498,169 @@ fn update_projectiles(
     }
 }
 
-fn update_health_bars(
-    mut health_bar_query: Query<(&mut Transform, &Parent), With<HealthBar>>,
-    health_query: Query<&Health>,
-) {
-    for (mut transform, parent) in &mut health_bar_query {
-        if let Ok(health) = health_query.get(parent.get()) {
-            let ratio = (health.current / health.max).max(0.0);
-            transform.scale.x = ratio * 2.0;
-        }
-    }
-}
-
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn handle_death(
     mut commands: Commands,
-    mut player_query: Query<(&mut Health, &mut Transform, &mut TargetPosition), With<Player>>,
-    enemy_query: Query<(Entity, &Health), (With<Enemy>, Without<Player>)>,
-    mut next_state: ResMut<NextState<GameState>>,
+    time: Res<Time>,
+    mut player_query: Query<(Entity, &Health, &mut CommandQueue), (With<Player>, Without<Respawning>)>,
+    enemy_query: Query<(Entity, &Health, &Transform), (With<Enemy>, Without<Player>)>,
+    mut dying_query: Query<(Entity, &mut Dying)>,
     mut score: ResMut<Score>,
     mut lives: ResMut<PlayerLives>,
+    mut score_events: EventWriter<ScoreEvent>,
 ) {
     // Handle enemies
-    for (entity, health) in &enemy_query {
+    for (entity, health, transform) in &enemy_query {
         if health.current <= 0.0 {
+            // The body stays for its death animation
+            commands.entity(entity)
+                .remove::<(Enemy, Health, AttackTimer)>()
+                .insert(Dying(Timer::from_seconds(DEATH_ANIMATION_SECS, TimerMode::Once)));
+            score.0 += KILL_SCORE;
+            score_events.send(ScoreEvent { position: transform.translation, points: KILL_SCORE });
+        }
+    }
+    for (entity, mut dying) in &mut dying_query {
+        if dying.0.tick(time.delta()).finished() {
             commands.entity(entity).despawn_recursive();
-            score.0 += 100;
         }
     }
 
     // Handle player
-    if let Ok((mut health, _, _)) = player_query.get_single_mut() {
+    if let Ok((entity, health, mut queue)) = player_query.get_single_mut() {
         if health.current <= 0.0 {
-            if lives.0 > 1 {
-                lives.0 -= 1;
-                health.current = health.max;
-                // Respawn slightly back or just keep current but reset health
-                // Let's just reset health and maybe move slightly to give breathing room
-                // transform.translation.x += 5.0; 
-                // target.0 = transform.translation;
+            lives.0 = lives.0.saturating_sub(1);
+            // The last life only waits out the death animation before the game ends
+            let delay = if lives.0 > 0 {
+                spawn_respawn_ui(&mut commands);
+                RESPAWN_DELAY_SECS
             } else {
-                lives.0 = 0;
-                next_state.set(GameState::GameOver);
-            }
+                DEATH_ANIMATION_SECS
+            };
+            // Orders don't carry over to the next life
+            queue.0.clear();
+            commands.entity(entity)
+                .remove::<(Invulnerable, AttackTarget, AttackMove, HoldPosition, Knockback)>()
+                .insert(Respawning(Timer::from_seconds(delay, TimerMode::Once)));
+        }
+    }
+}
+
+fn spawn_respawn_ui(commands: &mut Commands) {
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.3, 0.0, 0.0, 0.4)),
+        RespawnUI,
+        PlayingUI,
+    )).with_children(|parent| {
+        parent.spawn((
+            Text::new("YOU DIED"),
+            TextFont::from_font_size(60.0),
+            TextColor(Color::srgb(1.0, 0.2, 0.2)),
+        ));
+        parent.spawn((
+            Text::new(format!("Respawning in {}", RESPAWN_DELAY_SECS.ceil() as u32)),
+            TextFont::from_font_size(30.0),
+            TextColor(Color::WHITE),
+            RespawnCountdownUI,
+        ));
+    });
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn respawn_player(
+    mut commands: Commands,
+    time: Res<Time>,
+    progress: Res<Progress>,
+    lives: Res<PlayerLives>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut player_query: Query<(Entity, &mut Respawning, &mut Health, &mut Transform, &mut TargetPosition, &mut Visibility), With<Player>>,
+    projectile_query: Query<(Entity, &Transform, &Projectile), Without<Player>>,
+    mut countdown_query: Query<&mut Text, With<RespawnCountdownUI>>,
+    ui_query: Query<Entity, With<RespawnUI>>,
+) {
+    let Ok((entity, mut respawning, mut health, mut transform, mut target, mut visibility)) = player_query.get_single_mut() else {
+        return;
+    };
+
+    respawning.0.tick(time.delta());
+    if respawning.0.elapsed_secs() >= DEATH_ANIMATION_SECS {
+        *visibility = Visibility::Hidden;
+    }
+    for mut text in &mut countdown_query {
+        text.0 = format!("Respawning in {}", respawning.0.remaining_secs().ceil() as u32);
+    }
+
+    if !respawning.0.finished() {
+        return;
+    }
+    if lives.0 == 0 {
+        next_state.set(GameState::GameOver);
+        return;
+    }
+
+    // Put the player back slightly behind their furthest point, never past the wall
+    let spawn_x = (progress.min_x + RESPAWN_BACKOFF).min(progress.wall_x);
+    transform.translation = Vec3::new(spawn_x, 0.0, 0.0);
+    target.0 = transform.translation;
+    health.current = health.max;
+    health.shield = 0.0;
+    *visibility = Visibility::Visible;
+
+    // Give some breathing room by clearing incoming fire around the spawn point
+    for (projectile_entity, projectile_transform, projectile) in &projectile_query {
+        if !projectile.is_player
+            && projectile_transform.translation.distance(transform.translation) < RESPAWN_CLEAR_RADIUS
+        {
+            commands.entity(projectile_entity).despawn();
+        }
+    }
+
+    commands.entity(entity)
+        .remove::<Respawning>()
+        .insert(Invulnerable {
+            timer: Timer::from_seconds(RESPAWN_INVULNERABILITY_SECS, TimerMode::Once),
+            flash: Timer::from_seconds(0.1, TimerMode::Repeating),
+        });
+
+    for ui_entity in &ui_query {
+        commands.entity(ui_entity).despawn_recursive();
+    }
+}
+
+fn update_invulnerability(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut player_query: Query<(Entity, &mut Invulnerable, &mut Visibility), With<Player>>,
+) {
+    for (entity, mut invulnerable, mut visibility) in &mut player_query {
+        invulnerable.timer.tick(time.delta());
+        if invulnerable.timer.finished() {
+            *visibility = Visibility::Visible;
+            commands.entity(entity).remove::<Invulnerable>();
+            continue;
+        }
+
+        // Flash the model while the grace period lasts
+        if invulnerable.flash.tick(time.delta()).just_finished() {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Visible,
+                _ => Visibility::Hidden,
+            };
         }
     }
 }
 
 fn setup_game_over(mut commands: Commands) {
     commands.spawn((
-        Camera2d::default(),
+        Camera2d,
         MenuUI,
     ));
 
@@ -1025,65 +1691,35 @@ fn setup_game_over(mut commands: Commands) {
                 align_items: AlignItems::Center,
                 ..default()
             },
-            BackgroundColor(Color::srgb(0.15, 0.15, 0.15).into()),
+            BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
             MenuButton::Restart,
         )).with_child((Text::new("RESTART"), TextFont::from_font_size(30.0)));
     });
 }
 
+#[allow(clippy::type_complexity)]
 fn cleanup_playing(
     mut commands: Commands,
-    query: Query<Entity, Or<(With<Player>, With<Enemy>, With<Projectile>, With<Prop>, With<ClickIndicator>, With<Camera3d>, With<DirectionalLight>, With<PlayingUI>)>>,
+    query: Query<Entity, Or<(With<Player>, With<Enemy>, With<Dying>, With<Projectile>, With<Prop>, With<Ground>, With<ClickIndicator>, With<HoverOutline>, With<TargetMarker>, With<WaypointMarker>, With<RangeIndicator>, With<Camera3d>, With<DirectionalLight>, With<PlayingUI>)>>,
 ) {
     for entity in &query {
         commands.entity(entity).despawn_recursive();
     }
 }
 
-fn loop_environment(
-    mut commands: Commands,
-    mut player_query: Query<&mut Transform, With<Player>>,
-    mut target_query: Query<&mut TargetPosition, With<Player>>,
-    mut props_query: Query<&mut Transform, (With<Prop>, Without<Player>)>,
-    enemy_query: Query<Entity, With<Enemy>>,
-    mut progress: ResMut<Progress>,
-) {
-    let mut teleport_offset = 0.0;
-    for mut transform in &mut player_query {
-        if transform.translation.x < -350.0 {
-            teleport_offset = 700.0;
-            transform.translation.x += teleport_offset;
-            if let Ok(mut target) = target_query.get_single_mut() {
-                target.0.x += teleport_offset;
-            }
-            progress.min_x = transform.translation.x;
-            progress.wall_x = progress.min_x + 30.0;
-        }
-    }
-
-    if teleport_offset != 0.0 {
-        let mut rng = rand::thread_rng();
-        for mut prop_transform in &mut props_query {
-            // Reposition props to new "random" positions in the lane
-            let side = if prop_transform.translation.z > 0.0 { 1.0 } else { -1.0 };
-            prop_transform.translation.z = side * (LANE_HALF_WIDTH + rng.gen_range(-2.0..4.0));
-        }
-
-        // Clear enemies to keep it fresh
-        for entity in &enemy_query {
-            commands.entity(entity).despawn_recursive();
-        }
-    }
-}
-
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn handle_input(
-    mouse_button_input: Res<ButtonInput<MouseButton>>,
-    keys: Res<ButtonInput<KeyCode>>,
+    mut commands: Commands,
+    actions: Actions,
     window_query: Query<&Window>,
     camera_query: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
-    mut player_query: Query<(&mut TargetPosition, &Transform), With<Player>>,
+    mut player_query: Query<(Entity, &mut TargetPosition, &mut CommandQueue, &Transform), (With<Player>, Without<Respawning>)>,
     progress: Res<Progress>,
     mut hover_pos: ResMut<HoverPosition>,
+    mut attack_move_armed: ResMut<AttackMoveArmed>,
+    mut aim_preview: ResMut<AimPreview>,
+    scheme: Res<ControlScheme>,
+    minimap_query: MinimapInteraction,
 ) {
     let window = window_query.single();
     
@@ -1092,28 +1728,83 @@ fn handle_input(
         hover_pos.cursor = Some(cursor_position);
     }
 
+    let Ok((entity, mut target_pos, mut queue, transform)) = player_query.get_single_mut() else {
+        return;
+    };
+
+    let queueing = actions.pressed(Action::Queue);
+    // Clicks on the minimap pan the camera instead of giving orders
     let mut mouse_active = false;
-    
-    // Right Click Movement
-    if mouse_button_input.pressed(MouseButton::Right) {
+    let on_minimap = over_minimap(&minimap_query);
+
+    // Stop drops every order; hold also keeps the player planted
+    if actions.just_pressed(Action::Stop) || actions.just_pressed(Action::HoldPosition) {
+        queue.0.clear();
+        target_pos.0 = transform.translation;
+        attack_move_armed.armed = false;
+        aim_preview.area = None;
+        let mut player = commands.entity(entity);
+        player.remove::<(AttackTarget, AttackMove, HoldPosition)>();
+        if actions.just_pressed(Action::HoldPosition) {
+            player.insert(HoldPosition);
+        }
+        return;
+    }
+
+    // Attack-move: arm it, then fire to issue
+    if !actions.pressed(Action::Fire) {
+        attack_move_armed.issuing = false;
+    }
+    if actions.just_pressed(Action::AttackMove) {
+        attack_move_armed.armed = true;
+    }
+    if attack_move_armed.armed && actions.just_pressed(Action::Fire) && !on_minimap {
+        attack_move_armed.armed = false;
+        attack_move_armed.issuing = true;
+        if queueing {
+            queue.0.push_back(PlayerCommand::AttackMove(hover_pos.world));
+        } else {
+            queue.0.clear();
+            commands.entity(entity)
+                .remove::<(AttackTarget, HoldPosition)>()
+                .insert(AttackMove(hover_pos.world));
+        }
+    }
+
+    // Move on an enemy locks onto it instead of moving
+    if actions.pressed(Action::Move) && !on_minimap {
+        if let Some(enemy) = hover_pos.enemy {
+            mouse_active = true;
+            attack_move_armed.armed = false;
+            if !queueing {
+                clear_queue(&mut queue);
+                commands.entity(entity)
+                    .remove::<(AttackMove, HoldPosition)>()
+                    .insert(AttackTarget(enemy));
+            } else if actions.just_pressed(Action::Move) {
+                queue.0.push_back(PlayerCommand::Attack(enemy));
+            }
+        }
+    }
+
+    // Click movement
+    if actions.pressed(Action::Move) && scheme.click_moves() && !mouse_active && !on_minimap {
         if let Some(cursor) = hover_pos.cursor {
             if let Ok((camera, camera_transform)) = camera_query.get_single() {
-                if let Ok(ray) = camera.viewport_to_world(camera_transform, cursor) {
+                if let Ok(ray) = camera.viewport_to_world(camera_transform, window_to_viewport(camera, window, cursor)) {
                     let t = -ray.origin.y / ray.direction.y;
                     if t > 0.0 {
-                        let mut ground_pos = ray.origin + ray.direction * t;
-                        
-                        // Constrain X to be within the wall
-                        if ground_pos.x > progress.wall_x {
-                            ground_pos.x = progress.wall_x;
-                        }
-
-                        // Constrain Z to be within lane
-                        ground_pos.z = ground_pos.z.clamp(-PLAYER_BOUNDARY_Z, PLAYER_BOUNDARY_Z);
+                        let ground_pos = clamp_to_lane(ray.origin + ray.direction * t, &progress);
 
                         mouse_active = true;
-                        for (mut target_pos, _) in player_query.iter_mut() {
+                        attack_move_armed.armed = false;
+                        if !queueing {
+                            clear_queue(&mut queue);
                             target_pos.0 = ground_pos;
+                            commands.entity(entity).remove::<(AttackTarget, AttackMove, HoldPosition)>();
+                        } else if actions.just_pressed(Action::Move) {
+                            // Queued clicks append a waypoint
+                            queue.0.push_back(PlayerCommand::Move(ground_pos));
                         }
                     }
                 }
@@ -1121,52 +1812,372 @@ fn handle_input(
         }
     }
 
+    aim_preview.area = attack_move_armed.armed.then_some(ATTACK_MOVE_CURSOR_RADIUS);
+
     // Keyboard movement - only if mouse is not actively setting a target
-    if !mouse_active {
-        let mut keyboard_dir = Vec3::ZERO;
-        if keys.pressed(KeyCode::KeyW) || keys.pressed(KeyCode::ArrowUp) {
-            keyboard_dir.x -= 1.0;
+    if !mouse_active && scheme.keys_move() {
+        let keyboard_dir = keyboard_direction(&actions);
+        if keyboard_dir != Vec3::ZERO {
+            clear_queue(&mut queue);
+            target_pos.0 = transform.translation + keyboard_dir * 1.5;
+            commands.entity(entity).remove::<(AttackTarget, AttackMove, HoldPosition)>();
         }
-        if keys.pressed(KeyCode::KeyS) || keys.pressed(KeyCode::ArrowDown) {
-            keyboard_dir.x += 1.0;
+    }
+}
+
+/// Empties the queue, leaving it untouched when already empty so inputs held
+/// over many frames don't rebuild the waypoint markers every frame.
+fn clear_queue(queue: &mut Mut<CommandQueue>) {
+    if !queue.0.is_empty() {
+        queue.0.clear();
+    }
+}
+
+/// Normalized lane-space direction from the movement actions, or zero when idle.
+fn keyboard_direction(actions: &Actions) -> Vec3 {
+    let mut keyboard_dir = Vec3::ZERO;
+    if actions.pressed(Action::MoveUp) {
+        keyboard_dir.x -= 1.0;
+    }
+    if actions.pressed(Action::MoveDown) {
+        keyboard_dir.x += 1.0;
+    }
+    if actions.pressed(Action::MoveLeft) {
+        keyboard_dir.z += 1.0;
+    }
+    if actions.pressed(Action::MoveRight) {
+        keyboard_dir.z -= 1.0;
+    }
+    keyboard_dir.normalize_or_zero()
+}
+
+#[allow(clippy::type_complexity)]
+fn handle_dash(
+    mut commands: Commands,
+    time: Res<Time>,
+    actions: Actions,
+    hover_pos: Res<HoverPosition>,
+    ability_settings: Res<AbilitySettings>,
+    mut aim_preview: ResMut<AimPreview>,
+    mut player_query: Query<(Entity, &Transform, &TargetPosition, &mut DashCooldowns, &mut CommandQueue, Option<&mut Dashing>), (With<Player>, Without<Respawning>)>,
+) {
+    // Holding blink previews its path
+    aim_preview.line = actions.pressed(Action::Ability1).then_some(DASH_SPEED * DASH_DURATION_SECS);
+
+    let Ok((entity, transform, target, mut cooldowns, mut queue, dashing)) = player_query.get_single_mut() else {
+        return;
+    };
+
+    cooldowns.dash.tick(time.delta());
+    cooldowns.roll.tick(time.delta());
+
+    if let Some(mut dashing) = dashing {
+        if dashing.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Dashing>();
+        }
+        return;
+    }
+
+    let mut cursor_dir = hover_pos.world - transform.translation;
+    cursor_dir.y = 0.0;
+    let cursor_dir = cursor_dir.normalize_or_zero();
+
+    // Blink: always toward the cursor, or queued with shift
+    let blink = if ability_settings.blink_on_release {
+        actions.just_released(Action::Ability1)
+    } else {
+        actions.just_pressed(Action::Ability1)
+    };
+    if blink {
+        if actions.pressed(Action::Queue) {
+            queue.0.push_back(PlayerCommand::Dash(hover_pos.world));
+            return;
         }
-        if keys.pressed(KeyCode::KeyA) || keys.pressed(KeyCode::ArrowLeft) {
-            keyboard_dir.z += 1.0;
+        if cursor_dir != Vec3::ZERO && cooldowns.dash.finished() {
+            start_dash(&mut commands, entity, cursor_dir, &mut cooldowns);
+            return;
         }
-        if keys.pressed(KeyCode::KeyD) || keys.pressed(KeyCode::ArrowRight) {
-            keyboard_dir.z -= 1.0;
+    }
+
+    // Roll: keyboard direction, then current move order, then the cursor
+    if actions.just_pressed(Action::Ability2) && cooldowns.roll.finished() {
+        let mut move_dir = target.0 - transform.translation;
+        move_dir.y = 0.0;
+        let roll_dir = [keyboard_direction(&actions), move_dir.normalize_or_zero(), cursor_dir]
+            .into_iter()
+            .find(|dir| *dir != Vec3::ZERO);
+
+        if let Some(roll_dir) = roll_dir {
+            commands.entity(entity).insert(Dashing {
+                velocity: roll_dir * ROLL_SPEED,
+                timer: Timer::from_seconds(ROLL_DURATION_SECS, TimerMode::Once),
+            });
+            cooldowns.roll.reset();
         }
+    }
+}
 
-        if keyboard_dir != Vec3::ZERO {
-            let keyboard_dir = keyboard_dir.normalize();
-            for (mut target_pos, transform) in player_query.iter_mut() {
-                target_pos.0 = transform.translation + keyboard_dir * 1.5;
+fn start_dash(commands: &mut Commands, entity: Entity, dir: Vec3, cooldowns: &mut DashCooldowns) {
+    commands.entity(entity).insert(Dashing {
+        velocity: dir * DASH_SPEED,
+        timer: Timer::from_seconds(DASH_DURATION_SECS, TimerMode::Once),
+    });
+    cooldowns.dash.reset();
+}
+
+/// Starts the next queued command once the player has finished the current one.
+#[allow(clippy::type_complexity)]
+fn advance_command_queue(
+    mut commands: Commands,
+    progress: Res<Progress>,
+    mut player_query: Query<
+        (Entity, &Transform, &mut TargetPosition, &mut CommandQueue, &mut DashCooldowns),
+        (With<Player>, Without<Respawning>, Without<AttackTarget>, Without<AttackMove>, Without<Dashing>),
+    >,
+    enemy_query: Query<(), With<Enemy>>,
+) {
+    let Ok((entity, transform, mut target_pos, mut queue, mut cooldowns)) = player_query.get_single_mut() else {
+        return;
+    };
+
+    let mut remaining = target_pos.0 - transform.translation;
+    remaining.y = 0.0;
+    if remaining.length() > 0.2 {
+        return;
+    }
+
+    let Some(command) = queue.0.front().copied() else {
+        return;
+    };
+
+    match command {
+        PlayerCommand::Move(pos) => {
+            target_pos.0 = clamp_to_lane(pos, &progress);
+        }
+        PlayerCommand::Attack(enemy) => {
+            if enemy_query.contains(enemy) {
+                commands.entity(entity).insert(AttackTarget(enemy));
+            }
+        }
+        PlayerCommand::AttackMove(pos) => {
+            commands.entity(entity).insert(AttackMove(pos));
+        }
+        PlayerCommand::Dash(pos) => {
+            // Queued casts wait for the cooldown instead of being dropped
+            if !cooldowns.dash.finished() {
+                return;
+            }
+            let mut dir = pos - transform.translation;
+            dir.y = 0.0;
+            let dir = dir.normalize_or_zero();
+            if dir != Vec3::ZERO {
+                start_dash(&mut commands, entity, dir, &mut cooldowns);
+            }
+        }
+    }
+
+    queue.0.pop_front();
+    commands.entity(entity).remove::<HoldPosition>();
+}
+
+/// Rebuilds the waypoint chain whenever the queue changes, and keeps queued
+/// attack markers on their enemies.
+#[allow(clippy::type_complexity)]
+fn update_waypoint_markers(
+    mut commands: Commands,
+    waypoint_assets: Res<WaypointAssets>,
+    player_query: Query<(&Transform, &TargetPosition, Ref<CommandQueue>), With<Player>>,
+    mut marker_query: Query<(Entity, &WaypointMarker, &mut Transform), (Without<Player>, Without<Enemy>)>,
+    enemy_query: Query<&Transform, (With<Enemy>, Without<WaypointMarker>)>,
+    mut gizmos: Gizmos,
+) {
+    let Ok((transform, target_pos, queue)) = player_query.get_single() else {
+        return;
+    };
+
+    let points: Vec<(Vec3, Option<Entity>)> = queue.0.iter()
+        .filter_map(|command| match command {
+            PlayerCommand::Move(pos) | PlayerCommand::AttackMove(pos) | PlayerCommand::Dash(pos) => Some((*pos, None)),
+            PlayerCommand::Attack(enemy) => enemy_query.get(*enemy).ok().map(|t| (t.translation, Some(*enemy))),
+        })
+        .collect();
+
+    // Connect the player, the current target and every queued point
+    if !points.is_empty() {
+        let mut from = transform.translation;
+        for to in std::iter::once(target_pos.0).chain(points.iter().map(|(pos, _)| *pos)) {
+            gizmos.line(from + Vec3::Y * 0.1, to + Vec3::Y * 0.1, Color::srgb(0.0, 1.0, 1.0));
+            from = to;
+        }
+    }
+
+    if !queue.is_changed() {
+        for (_, marker, mut marker_transform) in &mut marker_query {
+            if let Some(enemy_transform) = marker.0.and_then(|enemy| enemy_query.get(enemy).ok()) {
+                marker_transform.translation = enemy_transform.translation.with_y(0.1);
             }
         }
+        return;
+    }
+
+    for (entity, _, _) in &marker_query {
+        commands.entity(entity).despawn();
+    }
+    for (pos, enemy) in points {
+        commands.spawn((
+            WaypointMarker(enemy),
+            Mesh3d(waypoint_assets.mesh.clone()),
+            MeshMaterial3d(waypoint_assets.material.clone()),
+            Transform::from_translation(Vec3::new(pos.x, 0.1, pos.z)),
+            Visibility::Visible,
+            InheritedVisibility::default(),
+        ));
     }
 }
 
+/// Clamps a ground point to the walkable part of the lane.
+fn clamp_to_lane(mut pos: Vec3, progress: &Progress) -> Vec3 {
+    pos.x = pos.x.min(progress.wall_x);
+    pos.z = pos.z.clamp(-PLAYER_BOUNDARY_Z, PLAYER_BOUNDARY_Z);
+    pos
+}
+
+/// Drives lock-on and attack-move orders by steering `TargetPosition`.
+#[allow(clippy::type_complexity)]
+fn update_attack_orders(
+    mut commands: Commands,
+    progress: Res<Progress>,
+    mut player_query: Query<(Entity, &Transform, &mut TargetPosition, Option<&AttackTarget>, Option<&AttackMove>, Has<HoldPosition>), (With<Player>, Without<Respawning>)>,
+    enemy_query: Query<(Entity, &Transform, &Health), (With<Enemy>, Without<Player>)>,
+    vision: Res<VisionGrid>,
+) {
+    let Ok((entity, transform, mut target_pos, attack_target, attack_move, holding)) = player_query.get_single_mut() else {
+        return;
+    };
+    let player_pos = transform.translation;
+
+    // Only living enemies the player can see are valid targets; a locked one
+    // slipping into the fog is dropped
+    let enemy_query = enemy_query.iter().filter(|(_, enemy_transform, health)| {
+        health.current > 0.0 && vision.is_visible(enemy_transform.translation)
+    });
+    let enemies: Vec<(Entity, &Transform, &Health)> = enemy_query.collect();
+
+    let mut locked = attack_target
+        .and_then(|target| enemies.iter().find(|(enemy, _, _)| *enemy == target.0))
+        .map(|(_, enemy_transform, _)| enemy_transform.translation);
+
+    if attack_target.is_some() && locked.is_none() {
+        commands.entity(entity).remove::<AttackTarget>();
+    }
+
+    // Holding position only engages what is already in range and never walks
+    if holding {
+        if locked.is_none_or(|pos| pos.distance(player_pos) > PLAYER_ATTACK_RANGE) {
+            let nearest = enemies
+                .iter()
+                .filter(|(_, enemy_transform, _)| enemy_transform.translation.distance(player_pos) <= PLAYER_ATTACK_RANGE)
+                .min_by(|(_, a, _), (_, b, _)| a.translation.distance(player_pos).total_cmp(&b.translation.distance(player_pos)));
+            match nearest {
+                Some((enemy, _, _)) => commands.entity(entity).insert(AttackTarget(*enemy)),
+                None => commands.entity(entity).remove::<AttackTarget>(),
+            };
+        }
+        target_pos.0 = player_pos;
+        return;
+    }
+
+    // Attack-move picks up the nearest enemy once the previous one is gone
+    if let (None, Some(attack_move)) = (locked, attack_move) {
+        let nearest = enemies
+            .iter()
+            .map(|(enemy, enemy_transform, _)| (*enemy, enemy_transform.translation))
+            .filter(|(_, pos)| pos.distance(player_pos) <= ATTACK_MOVE_ACQUIRE_RADIUS)
+            .min_by(|(_, a), (_, b)| a.distance(player_pos).total_cmp(&b.distance(player_pos)));
+
+        if let Some((enemy, enemy_pos)) = nearest {
+            commands.entity(entity).insert(AttackTarget(enemy));
+            locked = Some(enemy_pos);
+        } else if player_pos.distance(attack_move.0) < 0.5 {
+            commands.entity(entity).remove::<AttackMove>();
+        } else {
+            target_pos.0 = clamp_to_lane(attack_move.0, &progress);
+        }
+    }
+
+    if let Some(enemy_pos) = locked {
+        let offset = Vec3::new(player_pos.x - enemy_pos.x, 0.0, player_pos.z - enemy_pos.z);
+        if offset.length() > PLAYER_ATTACK_RANGE {
+            // Walk until just inside range
+            let approach = enemy_pos + offset.normalize() * (PLAYER_ATTACK_RANGE * 0.9);
+            target_pos.0 = clamp_to_lane(Vec3::new(approach.x, 0.0, approach.z), &progress);
+        } else {
+            target_pos.0 = player_pos;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_target_markers(
+    hover_pos: Res<HoverPosition>,
+    player_query: Query<Option<&AttackTarget>, With<Player>>,
+    enemy_query: Query<&Transform, (With<Enemy>, Without<HoverOutline>, Without<TargetMarker>)>,
+    mut outline_query: Query<(&mut Transform, &mut Visibility), (With<HoverOutline>, Without<TargetMarker>)>,
+    mut marker_query: Query<(&mut Transform, &mut Visibility), (With<TargetMarker>, Without<HoverOutline>)>,
+) {
+    let locked = player_query.get_single().ok().flatten().map(|target| target.0);
+
+    let hovered = hover_pos.enemy.and_then(|enemy| enemy_query.get(enemy).ok());
+    for (mut transform, mut visibility) in &mut outline_query {
+        match hovered {
+            Some(enemy_transform) if hover_pos.enemy != locked => {
+                transform.translation = enemy_transform.translation + Vec3::Y * 0.05;
+                *visibility = Visibility::Visible;
+            }
+            _ => *visibility = Visibility::Hidden,
+        }
+    }
+
+    let target = locked.and_then(|enemy| enemy_query.get(enemy).ok());
+    for (mut transform, mut visibility) in &mut marker_query {
+        match target {
+            Some(enemy_transform) => {
+                transform.translation = enemy_transform.translation + Vec3::Y * 0.06;
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
 fn move_player(
     time: Res<Time>,
-    mut query: Query<(&mut Transform, &TargetPosition), With<Player>>,
+    mut query: Query<(&mut Transform, &mut TargetPosition, Option<&Dashing>), (With<Player>, Without<Respawning>)>,
     mut progress: ResMut<Progress>,
+    layout: Res<LaneLayout>,
 ) {
-    for (mut transform, target) in query.iter_mut() {
-        let direction = target.0 - transform.translation;
-        let distance = direction.length();
-        
-        if distance > 0.1 {
-            let move_speed = 7.0;
-            let move_delta = direction.normalize() * move_speed * time.delta_secs();
-            
-            if move_delta.length() > distance {
-                transform.translation = target.0;
-            } else {
-                transform.translation += move_delta;
+    for (mut transform, mut target, dashing) in query.iter_mut() {
+        if let Some(dashing) = dashing {
+            // Dash overrides the move order; the player stops where it ends
+            transform.translation += dashing.velocity * time.delta_secs();
+            target.0 = transform.translation;
+        } else {
+            let direction = target.0 - transform.translation;
+            let distance = direction.length();
+
+            if distance > 0.1 {
+                let move_delta = direction.normalize() * PLAYER_MOVE_SPEED * time.delta_secs();
+
+                if move_delta.length() > distance {
+                    transform.translation = target.0;
+                } else {
+                    transform.translation += move_delta;
+                }
             }
         }
 
-        // Block from moving downwards (backwards)
+        // The wall blocks moving backwards and pushes the player when it advances
         if transform.translation.x > progress.wall_x {
             transform.translation.x = progress.wall_x;
         }
@@ -1174,10 +2185,17 @@ fn move_player(
         // Boundary check Z
         transform.translation.z = transform.translation.z.clamp(-PLAYER_BOUNDARY_Z, PLAYER_BOUNDARY_Z);
 
-        // Update progress and move wall forward
+        // Slide around lane obstacles
+        transform.translation = layout.resolve_collision(transform.translation, CHARACTER_RADIUS);
+
+        if dashing.is_some() {
+            target.0 = transform.translation;
+        }
+
+        // Update progress and drag the wall along; it never moves back
         if transform.translation.x < progress.min_x {
             progress.min_x = transform.translation.x;
-            progress.wall_x = progress.min_x + 15.0; // The "old platform" is left behind
+            progress.wall_x = progress.wall_x.min(progress.min_x + WALL_TRAIL);
         }
     }
 }
@@ -1186,6 +2204,7 @@ fn setup_playing_ui(
     mut commands: Commands,
     mut score: ResMut<Score>,
     mut lives: ResMut<PlayerLives>,
+    map: Res<ActionMap>,
 ) {
     commands.insert_resource(SurvivalTimer(Timer::from_seconds(300.0, TimerMode::Once)));
     score.0 = 0;
@@ -1221,9 +2240,22 @@ fn setup_playing_ui(
             TextColor(Color::srgb(1.0, 0.0, 0.0)),
             LivesUI,
         ));
+        parent.spawn((
+            Text::new(format!("Dash [{}]: Ready", ability_key(&map, Action::Ability1))),
+            TextFont::from_font_size(20.0),
+            TextColor(Color::srgb(0.0, 1.0, 1.0)),
+            DashUI,
+        ));
+        parent.spawn((
+            Text::new("Wall: 15.0 m"),
+            TextFont::from_font_size(20.0),
+            TextColor(Color::srgb(0.8, 0.5, 1.0)),
+            WallUI,
+        ));
     });
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn update_ui(
     time: Res<Time>,
     mut timer: ResMut<SurvivalTimer>,
@@ -1257,9 +2289,140 @@ fn update_ui(
     }
 }
 
+/// In advance mode the wall creeps forward on its own, pausing while the player respawns.
+fn advance_wall(
+    time: Res<Time>,
+    wall_settings: Res<WallSettings>,
+    mut progress: ResMut<Progress>,
+    player_query: Query<(), (With<Player>, Without<Respawning>)>,
+) {
+    if let (WallMode::Advance { speed }, Ok(())) = (wall_settings.mode, player_query.get_single()) {
+        progress.wall_x -= speed * time.delta_secs();
+    }
+}
+
+fn update_wall_ui(
+    progress: Res<Progress>,
+    player_query: Query<&Transform, With<Player>>,
+    mut wall_ui_query: Query<(&mut Text, &mut TextColor), With<WallUI>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let distance = (progress.wall_x - player_transform.translation.x).max(0.0);
+
+    for (mut text, mut color) in &mut wall_ui_query {
+        text.0 = format!("Wall: {:.1} m", distance);
+        color.0 = if distance < WALL_WARNING_DISTANCE { Color::srgb(1.0, 0.2, 0.2) } else { Color::srgb(0.8, 0.5, 1.0) };
+    }
+}
+
+fn toggle_range_indicators(
+    actions: Actions,
+    mut settings: ResMut<RangeIndicatorSettings>,
+    mut debug: ResMut<DebugMode>,
+) {
+    if actions.just_pressed(Action::ToggleRange) {
+        settings.show_attack_range = !settings.show_attack_range;
+    }
+    if actions.just_pressed(Action::ToggleDebug) {
+        debug.0 = !debug.0;
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_range_indicators(
+    settings: Res<RangeIndicatorSettings>,
+    aim_preview: Res<AimPreview>,
+    hover_pos: Res<HoverPosition>,
+    player_query: Query<&Transform, (With<Player>, Without<Respawning>, Without<RangeIndicator>)>,
+    mut indicator_query: Query<(&RangeIndicator, &mut Transform, &mut Visibility)>,
+) {
+    let player_pos = player_query.get_single().ok().map(|transform| transform.translation);
+
+    for (indicator, mut transform, mut visibility) in &mut indicator_query {
+        let shown = match (indicator, player_pos) {
+            (RangeIndicator::AttackRange, Some(player_pos)) if settings.show_attack_range => {
+                transform.translation = Vec3::new(player_pos.x, 0.03, player_pos.z);
+                true
+            }
+            (RangeIndicator::Skillshot, Some(player_pos)) => {
+                let mut dir = hover_pos.world - player_pos;
+                dir.y = 0.0;
+                match (aim_preview.line, dir.try_normalize()) {
+                    (Some(length), Some(dir)) => {
+                        let start = Vec3::new(player_pos.x, 0.04, player_pos.z);
+                        *transform = Transform::from_translation(start + dir * (length / 2.0))
+                            .looking_to(dir, Vec3::Y)
+                            .with_scale(Vec3::new(1.2, 1.0, length));
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            (RangeIndicator::CursorArea, _) => match aim_preview.area {
+                Some(radius) => {
+                    transform.translation = Vec3::new(hover_pos.world.x, 0.05, hover_pos.world.z);
+                    transform.scale = Vec3::splat(radius);
+                    true
+                }
+                None => false,
+            },
+            _ => false,
+        };
+
+        *visibility = if shown { Visibility::Visible } else { Visibility::Hidden };
+    }
+}
+
+fn draw_enemy_aggro_ranges(
+    debug: Res<DebugMode>,
+    weather: Res<CurrentWeather>,
+    enemy_query: Query<&Transform, With<Enemy>>,
+    mut gizmos: Gizmos,
+) {
+    if !debug.0 {
+        return;
+    }
+
+    for transform in &enemy_query {
+        let center = Vec3::new(transform.translation.x, 0.05, transform.translation.z);
+        gizmos.circle(
+            Isometry3d::new(center, Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+            ENEMY_ATTACK_RANGE * weather.0.def().aggro_scale,
+            Color::srgba(1.0, 0.2, 0.2, 0.5),
+        );
+    }
+}
+
+fn update_dash_ui(
+    map: Res<ActionMap>,
+    player_query: Query<&DashCooldowns, With<Player>>,
+    mut dash_query: Query<&mut Text, With<DashUI>>,
+) {
+    let Ok(cooldowns) = player_query.get_single() else {
+        return;
+    };
+
+    // Rebuilt every frame, so rebinding shows up straight away
+    let key = ability_key(&map, Action::Ability1);
+    for mut text in &mut dash_query {
+        text.0 = if cooldowns.dash.finished() {
+            format!("Dash [{key}]: Ready")
+        } else {
+            format!("Dash [{key}]: {:.1}s", cooldowns.dash.remaining_secs())
+        };
+    }
+}
+
+/// First key bound to `action`, named as on the bindings screen.
+fn ability_key(map: &ActionMap, action: Action) -> String {
+    map.bindings(action).first().map_or("-".to_string(), |binding| binding.label())
+}
+
 fn setup_victory(mut commands: Commands) {
     commands.spawn((
-        Camera2d::default(),
+        Camera2d,
         MenuUI,
     ));
 
@@ -1291,7 +2454,7 @@ fn setup_victory(mut commands: Commands) {
                 align_items: AlignItems::Center,
                 ..default()
             },
-            BackgroundColor(Color::srgb(0.15, 0.15, 0.15).into()),
+            BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
             MenuButton::Restart,
         )).with_child((Text::new("PLAY AGAIN"), TextFont::from_font_size(30.0)));
     });
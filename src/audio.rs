@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+
+use bevy::audio::{PlaybackMode, Volume};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{Boss, DamageEvent, DamageKind, Dying, Enemy, GameState, HealEvent, Player, Respawning, ShotEvent};
+use crate::camera::CameraRig;
+
+/// Seconds for music to fade in or out when the track changes.
+const MUSIC_FADE_SECS: f32 = 1.0;
+/// Sounds closer than this to the camera's focus play at full volume,
+/// fading to silence at `SFX_FAR`.
+const SFX_NEAR: f32 = 10.0;
+const SFX_FAR: f32 = 60.0;
+/// Beyond this many sound effects playing at once, new ones are dropped.
+const MAX_SOUND_EFFECTS: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MusicTrack {
+    Menu,
+    Playing,
+    /// Plays over `Playing` while a boss is alive.
+    Boss,
+    GameOver,
+    Victory,
+}
+
+impl MusicTrack {
+    const ALL: [MusicTrack; 5] = [MusicTrack::Menu, MusicTrack::Playing, MusicTrack::Boss, MusicTrack::GameOver, MusicTrack::Victory];
+
+    fn path(self) -> &'static str {
+        match self {
+            MusicTrack::Menu => "audio/music_menu.wav",
+            MusicTrack::Playing => "audio/music_playing.wav",
+            MusicTrack::Boss => "audio/music_boss.wav",
+            MusicTrack::GameOver => "audio/music_game_over.wav",
+            MusicTrack::Victory => "audio/music_victory.wav",
+        }
+    }
+
+    fn for_state(state: &GameState, boss_fight: bool) -> Option<Self> {
+        match state {
+            GameState::Loading => None,
+            GameState::Menu => Some(MusicTrack::Menu),
+            GameState::Playing if boss_fight => Some(MusicTrack::Boss),
+            GameState::Playing | GameState::Restarting => Some(MusicTrack::Playing),
+            GameState::GameOver => Some(MusicTrack::GameOver),
+            GameState::Victory => Some(MusicTrack::Victory),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sfx {
+    Shoot,
+    Hit,
+    Hurt,
+    Death,
+    Pickup,
+    Click,
+}
+
+impl Sfx {
+    const ALL: [Sfx; 6] = [Sfx::Shoot, Sfx::Hit, Sfx::Hurt, Sfx::Death, Sfx::Pickup, Sfx::Click];
+
+    fn path(self) -> &'static str {
+        match self {
+            Sfx::Shoot => "audio/sfx_shoot.wav",
+            Sfx::Hit => "audio/sfx_hit.wav",
+            Sfx::Hurt => "audio/sfx_hurt.wav",
+            Sfx::Death => "audio/sfx_death.wav",
+            Sfx::Pickup => "audio/sfx_pickup.wav",
+            Sfx::Click => "audio/sfx_click.wav",
+        }
+    }
+
+    /// Relative loudness, so rapid fire doesn't drown out everything else.
+    fn volume(self) -> f32 {
+        match self {
+            Sfx::Shoot => 0.4,
+            Sfx::Hit => 0.6,
+            Sfx::Click => 0.5,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Music and sound effect files, for preloading.
+pub fn audio_asset_paths() -> Vec<&'static str> {
+    MusicTrack::ALL.map(MusicTrack::path).into_iter().chain(Sfx::ALL.map(Sfx::path)).collect()
+}
+
+/// Volume buses, 0 to 1. Music and sound effects are both scaled by `master`.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self { master: 0.8, music: 0.6, sfx: 0.8 }
+    }
+}
+
+/// Browsers keep WebAudio suspended until the first user gesture, so on the
+/// web nothing is started before then (`index.html` resumes the context).
+#[derive(Resource)]
+pub struct AudioUnlocked(pub bool);
+
+impl Default for AudioUnlocked {
+    fn default() -> Self {
+        Self(!cfg!(target_arch = "wasm32"))
+    }
+}
+
+#[derive(Resource)]
+pub struct AudioAssets {
+    music: HashMap<MusicTrack, Handle<AudioSource>>,
+    sfx: HashMap<Sfx, Handle<AudioSource>>,
+}
+
+/// A looping music track, faded in while it is the state's track and out
+/// (then despawned) once it isn't.
+#[derive(Component)]
+pub struct Music {
+    track: MusicTrack,
+    level: f32,
+}
+
+#[derive(Component)]
+pub struct SoundEffect;
+
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<AudioSettings>()
+            .init_resource::<AudioUnlocked>()
+            .add_systems(Startup, setup_audio)
+            .add_systems(Update, (
+                unlock_audio,
+                update_music,
+                play_sound_effects,
+            ).chain());
+    }
+}
+
+fn setup_audio(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(AudioAssets {
+        music: MusicTrack::ALL.into_iter().map(|track| (track, asset_server.load(track.path()))).collect(),
+        sfx: Sfx::ALL.into_iter().map(|sfx| (sfx, asset_server.load(sfx.path()))).collect(),
+    });
+}
+
+fn unlock_audio(
+    mut unlocked: ResMut<AudioUnlocked>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+) {
+    if !unlocked.0
+        && (keys.get_just_pressed().next().is_some()
+            || mouse_button_input.get_just_pressed().next().is_some()
+            || touches.any_just_pressed())
+    {
+        unlocked.0 = true;
+    }
+}
+
+/// Crossfades to the current state's track. Runs on real time so fades
+/// carry on through hit-stops.
+#[allow(clippy::too_many_arguments)]
+fn update_music(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    state: Res<State<GameState>>,
+    settings: Res<AudioSettings>,
+    unlocked: Res<AudioUnlocked>,
+    audio_assets: Res<AudioAssets>,
+    boss_query: Query<(), (With<Boss>, With<Enemy>)>,
+    mut music_query: Query<(Entity, &mut Music, Option<&AudioSink>)>,
+) {
+    let wanted = MusicTrack::for_state(state.get(), !boss_query.is_empty());
+    let step = time.delta_secs() / MUSIC_FADE_SECS;
+
+    let mut playing = false;
+    for (entity, mut music, sink) in &mut music_query {
+        if Some(music.track) == wanted {
+            music.level = (music.level + step).min(1.0);
+            playing = true;
+        } else {
+            music.level -= step;
+            if music.level <= 0.0 {
+                commands.entity(entity).despawn();
+                continue;
+            }
+        }
+        if let Some(sink) = sink {
+            sink.set_volume(music.level * settings.music * settings.master);
+        }
+    }
+
+    if let (Some(track), false, true) = (wanted, playing, unlocked.0) {
+        commands.spawn((
+            Music { track, level: 0.0 },
+            AudioPlayer::new(audio_assets.music[&track].clone()),
+            PlaybackSettings {
+                mode: PlaybackMode::Loop,
+                volume: Volume::new(0.0),
+                ..default()
+            },
+        ));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn play_sound_effects(
+    mut commands: Commands,
+    settings: Res<AudioSettings>,
+    unlocked: Res<AudioUnlocked>,
+    audio_assets: Res<AudioAssets>,
+    rig: Res<CameraRig>,
+    mut shot_events: EventReader<ShotEvent>,
+    mut damage_events: EventReader<DamageEvent>,
+    mut heal_events: EventReader<HealEvent>,
+    player_query: Query<(), With<Player>>,
+    transform_query: Query<&Transform>,
+    dying_query: Query<&Transform, Added<Dying>>,
+    respawning_query: Query<&Transform, (With<Player>, Added<Respawning>)>,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<Button>)>,
+    sound_query: Query<(), With<SoundEffect>>,
+) {
+    // Falls off with distance from where the camera is looking
+    let attenuation = |pos: Vec3| 1.0 - ((pos.distance(rig.current_focus) - SFX_NEAR) / (SFX_FAR - SFX_NEAR)).clamp(0.0, 1.0);
+
+    // Each sound plays at most once a frame, as loud as its loudest cause
+    let mut sounds: HashMap<Sfx, f32> = HashMap::new();
+    let mut add = |sfx: Sfx, volume: f32| {
+        let loudest = sounds.entry(sfx).or_default();
+        *loudest = loudest.max(volume);
+    };
+
+    for event in shot_events.read() {
+        add(Sfx::Shoot, attenuation(event.position));
+    }
+    for event in damage_events.read() {
+        if event.kind == DamageKind::OverTime {
+            continue;
+        }
+        let sfx = if player_query.contains(event.target) { Sfx::Hurt } else { Sfx::Hit };
+        add(sfx, attenuation(event.position));
+    }
+    for event in heal_events.read() {
+        if let Ok(transform) = transform_query.get(event.target) {
+            add(Sfx::Pickup, attenuation(transform.translation));
+        }
+    }
+    for transform in dying_query.iter().chain(&respawning_query) {
+        add(Sfx::Death, attenuation(transform.translation));
+    }
+    if button_query.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        add(Sfx::Click, 1.0);
+    }
+
+    if !unlocked.0 {
+        return;
+    }
+    let mut playing = sound_query.iter().count();
+    for (sfx, volume) in sounds {
+        let volume = volume * sfx.volume() * settings.sfx * settings.master;
+        if volume <= 0.0 || playing >= MAX_SOUND_EFFECTS {
+            continue;
+        }
+        playing += 1;
+        commands.spawn((
+            SoundEffect,
+            AudioPlayer::new(audio_assets.sfx[&sfx].clone()),
+            PlaybackSettings::DESPAWN.with_volume(Volume::new(volume)),
+        ));
+    }
+}
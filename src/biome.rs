@@ -0,0 +1,391 @@
+use bevy::image::{ImageAddressMode, ImageLoaderSettings, ImageSampler, ImageSamplerDescriptor};
+use bevy::pbr::{DistanceFog, FogFalloff};
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::app::{EnemyArchetype, GameState, PlayState, Player, PlayingUI, RunSeed};
+use crate::daynight::{tinted, TimeOfDay};
+use crate::lane::LaneLayout;
+use crate::weather::{CurrentWeather, Weather};
+
+/// Number of lane segments a biome lasts before the next one starts.
+pub const BIOME_SECTION_SEGMENTS: i32 = 10;
+/// How quickly lighting and fog blend toward the current biome (per second).
+const BIOME_BLEND_RATE: f32 = 0.5;
+const BIOME_BANNER_SECS: f32 = 3.0;
+/// Width of a biome ground strip across the lane.
+pub const BIOME_GROUND_WIDTH: f32 = 200.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Biome {
+    City,
+    ConstructionSite,
+    Park,
+    Industrial,
+}
+
+impl Biome {
+    pub const ALL: [Biome; 4] = [Biome::City, Biome::ConstructionSite, Biome::Park, Biome::Industrial];
+
+    pub fn def(self) -> &'static BiomeDef {
+        match self {
+            Biome::City => &CITY,
+            Biome::ConstructionSite => &CONSTRUCTION_SITE,
+            Biome::Park => &PARK,
+            Biome::Industrial => &INDUSTRIAL,
+        }
+    }
+
+    /// Section (run of `BIOME_SECTION_SEGMENTS` segments) containing lane segment `index`.
+    pub fn section(index: i32) -> i32 {
+        index.div_euclid(BIOME_SECTION_SEGMENTS)
+    }
+
+    /// Biome of the section containing lane segment `index`. The opening
+    /// section is always the city; later ones never repeat back to back.
+    pub fn for_segment(seed: u64, index: i32) -> Biome {
+        let section = Self::section(index);
+        let mut current = 0;
+        for step in 1..=section {
+            let mut rng = StdRng::seed_from_u64(seed.rotate_left(17) ^ step as u64);
+            current = (current + rng.gen_range(1..Self::ALL.len())) % Self::ALL.len();
+        }
+        Self::ALL[current]
+    }
+}
+
+/// What an enemy spawned in a biome looks like and how tough it is.
+pub struct SpawnEntry {
+    pub model: &'static str,
+    pub archetype: EnemyArchetype,
+    pub weight: u32,
+    pub health: f32,
+}
+
+/// Which roadside dressing a biome favours.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PropSet {
+    Buildings,
+    Trees,
+    Construction,
+    Industrial,
+}
+
+pub struct BiomeDef {
+    pub name: &'static str,
+    pub ground_texture: &'static str,
+    pub ground_tint: Color,
+    pub light_color: Color,
+    pub ambient_color: Color,
+    pub fog_color: Color,
+    pub fog_start: f32,
+    pub fog_end: f32,
+    /// Relative odds of each lane segment kind, in `SegmentKind` declaration order.
+    pub segment_weights: [u32; 7],
+    pub prop_set: PropSet,
+    /// Chance a roadside slot gets a building rather than the biome's own props.
+    pub building_chance: f64,
+    /// Relative odds of each weather, in `Weather::ALL` order.
+    pub weather_weights: [u32; 4],
+    pub spawn_table: &'static [SpawnEntry],
+}
+
+impl BiomeDef {
+    pub fn pick_enemy(&self, rng: &mut impl Rng) -> &SpawnEntry {
+        let total: u32 = self.spawn_table.iter().map(|entry| entry.weight).sum();
+        let mut roll = rng.gen_range(0..total);
+        for entry in self.spawn_table {
+            if roll < entry.weight {
+                return entry;
+            }
+            roll -= entry.weight;
+        }
+        &self.spawn_table[0]
+    }
+
+    /// The toughest enemy in the spawn table, which bosses are built from.
+    pub fn boss(&self) -> &SpawnEntry {
+        self.spawn_table.iter().max_by(|a, b| a.health.total_cmp(&b.health)).unwrap_or(&self.spawn_table[0])
+    }
+}
+
+static CITY: BiomeDef = BiomeDef {
+    name: "City Streets",
+    ground_texture: "PNG/Default/terrain_sand_a.png",
+    ground_tint: Color::srgb(0.6, 0.6, 0.62),
+    light_color: Color::srgb(1.0, 0.98, 0.92),
+    ambient_color: Color::WHITE,
+    fog_color: Color::srgba(0.7, 0.75, 0.8, 1.0),
+    fog_start: 80.0,
+    fog_end: 220.0,
+    segment_weights: [30, 15, 15, 15, 10, 10, 5],
+    prop_set: PropSet::Buildings,
+    building_chance: 0.85,
+    weather_weights: [50, 30, 15, 5],
+    spawn_table: &[
+        SpawnEntry { model: "Models/GLB_format/character-p.glb", archetype: EnemyArchetype::Scout, weight: 3, health: 100.0 },
+        SpawnEntry { model: "Models/GLB_format/character-q.glb", archetype: EnemyArchetype::Scout, weight: 3, health: 100.0 },
+        SpawnEntry { model: "Models/GLB_format/character-n.glb", archetype: EnemyArchetype::Grunt, weight: 2, health: 120.0 },
+        SpawnEntry { model: "Models/GLB_format/character-m.glb", archetype: EnemyArchetype::Grunt, weight: 2, health: 120.0 },
+    ],
+};
+
+static CONSTRUCTION_SITE: BiomeDef = BiomeDef {
+    name: "Construction Site",
+    ground_texture: "PNG/Default/terrain_dirt_a.png",
+    ground_tint: Color::srgb(0.85, 0.65, 0.4),
+    light_color: Color::srgb(1.0, 0.9, 0.75),
+    ambient_color: Color::srgb(1.0, 0.92, 0.8),
+    fog_color: Color::srgba(0.8, 0.7, 0.55, 1.0),
+    fog_start: 65.0,
+    fog_end: 180.0,
+    segment_weights: [25, 5, 10, 10, 5, 5, 40],
+    prop_set: PropSet::Construction,
+    building_chance: 0.4,
+    weather_weights: [40, 15, 10, 35],
+    spawn_table: &[
+        SpawnEntry { model: "Models/GLB_format/character-e.glb", archetype: EnemyArchetype::Grunt, weight: 3, health: 110.0 },
+        SpawnEntry { model: "Models/GLB_format/character-f.glb", archetype: EnemyArchetype::Grunt, weight: 3, health: 110.0 },
+        SpawnEntry { model: "Models/GLB_format/character-g.glb", archetype: EnemyArchetype::Brute, weight: 2, health: 140.0 },
+    ],
+};
+
+static PARK: BiomeDef = BiomeDef {
+    name: "City Park",
+    ground_texture: "PNG/Default/terrain_sand_top_a.png",
+    ground_tint: Color::srgb(0.5, 0.7, 0.5),
+    light_color: Color::srgb(1.0, 1.0, 0.9),
+    ambient_color: Color::srgb(0.9, 1.0, 0.9),
+    fog_color: Color::srgba(0.65, 0.8, 0.7, 1.0),
+    fog_start: 90.0,
+    fog_end: 250.0,
+    segment_weights: [45, 20, 5, 5, 5, 20, 0],
+    prop_set: PropSet::Trees,
+    building_chance: 0.15,
+    weather_weights: [45, 30, 25, 0],
+    spawn_table: &[
+        SpawnEntry { model: "Models/GLB_format/character-c.glb", archetype: EnemyArchetype::Scout, weight: 3, health: 90.0 },
+        SpawnEntry { model: "Models/GLB_format/character-d.glb", archetype: EnemyArchetype::Scout, weight: 3, health: 90.0 },
+        SpawnEntry { model: "Models/GLB_format/character-h.glb", archetype: EnemyArchetype::Grunt, weight: 2, health: 100.0 },
+    ],
+};
+
+static INDUSTRIAL: BiomeDef = BiomeDef {
+    name: "Industrial Zone",
+    ground_texture: "PNG/Default/terrain_dirt_c.png",
+    ground_tint: Color::srgb(0.45, 0.45, 0.45),
+    light_color: Color::srgb(0.95, 0.85, 0.8),
+    ambient_color: Color::srgb(0.8, 0.8, 0.85),
+    fog_color: Color::srgba(0.45, 0.45, 0.5, 1.0),
+    fog_start: 55.0,
+    fog_end: 160.0,
+    segment_weights: [25, 5, 25, 20, 10, 5, 10],
+    prop_set: PropSet::Industrial,
+    building_chance: 0.5,
+    weather_weights: [30, 20, 35, 15],
+    spawn_table: &[
+        SpawnEntry { model: "Models/GLB_format/character-i.glb", archetype: EnemyArchetype::Grunt, weight: 2, health: 130.0 },
+        SpawnEntry { model: "Models/GLB_format/character-j.glb", archetype: EnemyArchetype::Grunt, weight: 2, health: 130.0 },
+        SpawnEntry { model: "Models/GLB_format/character-k.glb", archetype: EnemyArchetype::Brute, weight: 2, health: 150.0 },
+        SpawnEntry { model: "Models/GLB_format/character-l.glb", archetype: EnemyArchetype::Brute, weight: 1, health: 180.0 },
+    ],
+};
+
+/// Biome the player is currently in.
+#[derive(Resource)]
+pub struct CurrentBiome(pub Biome);
+
+impl Default for CurrentBiome {
+    fn default() -> Self {
+        Self(Biome::City)
+    }
+}
+
+/// Ground materials, one per biome, used for the per-segment ground strips.
+#[derive(Resource)]
+pub struct BiomeAssets {
+    pub ground_mesh: Handle<Mesh>,
+    pub ground_materials: [Handle<StandardMaterial>; 4],
+}
+
+impl BiomeAssets {
+    pub fn ground_material(&self, biome: Biome) -> Handle<StandardMaterial> {
+        let index = Biome::ALL.iter().position(|b| *b == biome).unwrap_or(0);
+        self.ground_materials[index].clone()
+    }
+}
+
+#[derive(Component)]
+pub struct BiomeBanner(pub Timer);
+
+pub struct BiomePlugin;
+
+impl Plugin for BiomePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<CurrentBiome>()
+            .add_systems(OnEnter(GameState::Loading), load_biome_assets)
+            .add_systems(OnEnter(GameState::Playing), reset_biome)
+            .add_systems(Update, (
+                update_current_biome,
+                blend_biome_atmosphere,
+                update_biome_banner,
+            ).run_if(in_state(PlayState::Running)));
+    }
+}
+
+/// Every asset a biome can reference, for preloading.
+pub fn biome_asset_paths() -> impl Iterator<Item = &'static str> {
+    Biome::ALL.into_iter().flat_map(|biome| {
+        let def = biome.def();
+        std::iter::once(def.ground_texture).chain(def.spawn_table.iter().map(|entry| entry.model))
+    })
+}
+
+fn load_biome_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // One segment long, wide enough to reach past the roadside props
+    let mut ground_mesh = Plane3d::default().mesh().size(1.0, 1.0).build();
+    if let Some(bevy::render::mesh::VertexAttributeValues::Float32x2(ref mut uvs)) = ground_mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0) {
+        for uv in uvs {
+            uv[0] *= crate::lane::SEGMENT_LENGTH / 10.0;
+            uv[1] *= BIOME_GROUND_WIDTH / 10.0;
+        }
+    }
+
+    let ground_materials = Biome::ALL.map(|biome| {
+        let def = biome.def();
+        materials.add(StandardMaterial {
+            base_color: def.ground_tint,
+            base_color_texture: Some(asset_server.load_with_settings(
+                def.ground_texture,
+                |s: &mut ImageLoaderSettings| {
+                    s.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+                        address_mode_u: ImageAddressMode::Repeat,
+                        address_mode_v: ImageAddressMode::Repeat,
+                        ..default()
+                    });
+                },
+            )),
+            perceptual_roughness: 1.0,
+            reflectance: 0.0,
+            ..default()
+        })
+    });
+
+    commands.insert_resource(BiomeAssets {
+        ground_mesh: meshes.add(ground_mesh),
+        ground_materials,
+    });
+}
+
+fn reset_biome(mut current: ResMut<CurrentBiome>) {
+    current.0 = Biome::City;
+}
+
+fn update_current_biome(
+    mut commands: Commands,
+    run_seed: Res<RunSeed>,
+    layout: Res<LaneLayout>,
+    mut current: ResMut<CurrentBiome>,
+    player_query: Query<&Transform, With<Player>>,
+    banner_query: Query<Entity, With<BiomeBanner>>,
+) {
+    let Ok(transform) = player_query.get_single() else {
+        return;
+    };
+
+    let index = layout.index_at(transform.translation.x);
+    let biome = Biome::for_segment(run_seed.0, index);
+    if biome == current.0 {
+        return;
+    }
+    let weather = Weather::for_segment(run_seed.0, index);
+    current.0 = biome;
+
+    for entity in &banner_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(15.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BiomeBanner(Timer::from_seconds(BIOME_BANNER_SECS, TimerMode::Once)),
+        PlayingUI,
+    )).with_child((
+        Text::new(format!("Entering {} - {}", biome.def().name, weather.def().name)),
+        TextFont::from_font_size(40.0),
+        TextColor(Color::WHITE),
+    ));
+}
+
+/// Eases lighting and fog toward the current biome, tinted for the time of day.
+fn blend_biome_atmosphere(
+    time: Res<Time>,
+    current: Res<CurrentBiome>,
+    time_of_day: Res<TimeOfDay>,
+    weather: Res<CurrentWeather>,
+    mut ambient: ResMut<AmbientLight>,
+    mut light_query: Query<&mut DirectionalLight>,
+    mut fog_query: Query<&mut DistanceFog>,
+) {
+    let def = current.0.def();
+    let t = (BIOME_BLEND_RATE * time.delta_secs()).min(1.0);
+
+    let ambient_tint = time_of_day.ambient_tint();
+    ambient.color = ambient.color.mix(&tinted(def.ambient_color, ambient_tint), t);
+    for mut light in &mut light_query {
+        light.color = light.color.mix(&tinted(def.light_color, time_of_day.sun_tint()), t);
+    }
+    for mut fog in &mut fog_query {
+        let weather = weather.0.def();
+        fog.color = fog.color.mix(&tinted(tinted(def.fog_color, ambient_tint), weather.fog_tint), t);
+        if let FogFalloff::Linear { start, end } = &mut fog.falloff {
+            *start += (def.fog_start * weather.fog_scale - *start) * t;
+            *end += (def.fog_end * weather.fog_scale - *end) * t;
+        }
+    }
+}
+
+fn update_biome_banner(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut banner_query: Query<(Entity, &mut BiomeBanner, &Children)>,
+    mut text_query: Query<&mut TextColor>,
+) {
+    for (entity, mut banner, children) in &mut banner_query {
+        banner.0.tick(time.delta());
+        if banner.0.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let alpha = banner.0.remaining_secs().min(1.0);
+        for child in children.iter() {
+            if let Ok(mut color) = text_query.get_mut(*child) {
+                color.0 = color.0.with_alpha(alpha);
+            }
+        }
+    }
+}
+
+/// Fog matching the opening biome, for the gameplay camera.
+pub fn default_fog() -> DistanceFog {
+    let def = Biome::City.def();
+    DistanceFog {
+        color: def.fog_color,
+        falloff: FogFalloff::Linear {
+            start: def.fog_start,
+            end: def.fog_end,
+        },
+        ..default()
+    }
+}
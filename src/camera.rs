@@ -0,0 +1,202 @@
+use bevy::input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{GameState, PlayState, Player, Progress, LANE_HALF_WIDTH};
+use crate::graphics::window_to_viewport;
+use crate::input::{Action, Actions};
+
+/// Camera offset from the point it looks at, at zoom 1.
+const CAMERA_OFFSET: Vec3 = Vec3::new(20.0, 20.0, 20.0);
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 1.6;
+/// Zoom change per wheel line.
+const ZOOM_STEP: f32 = 0.1;
+/// How quickly the camera catches up with its focus and zoom (per second).
+const CAMERA_SMOOTHING: f32 = 10.0;
+/// Cursor distance from the window edge, in pixels, that starts edge scrolling.
+const EDGE_SCROLL_MARGIN: f32 = 12.0;
+const EDGE_SCROLL_SPEED: f32 = 30.0;
+/// How far ahead of the player and to the side an unlocked camera may wander.
+const MAX_PAN_AHEAD: f32 = 80.0;
+const MAX_PAN_SIDE: f32 = LANE_HALF_WIDTH * 2.0;
+
+/// MOBA-style camera. Locked, it follows the player's progress; unlocked (Y),
+/// it stays where it was panned with the screen edges, middle drag or the
+/// minimap. Holding Tab brings it back to the player either way.
+#[derive(Resource)]
+pub struct CameraRig {
+    pub locked: bool,
+    /// Ground point the camera is heading for.
+    pub focus: Vec3,
+    /// Multiplies the camera's distance from its focus.
+    pub zoom: f32,
+    /// Smoothed focus and zoom the camera is actually at.
+    pub current_focus: Vec3,
+    pub current_zoom: f32,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        Self {
+            locked: true,
+            focus: Vec3::ZERO,
+            zoom: 1.0,
+            current_focus: Vec3::ZERO,
+            current_zoom: 1.0,
+        }
+    }
+}
+
+impl CameraRig {
+    /// Detaches the camera and points it at `pos`.
+    pub fn pan_to(&mut self, pos: Vec3) {
+        self.locked = false;
+        self.focus = pos;
+    }
+
+    /// Shifts every stored position, for the floating-origin rebase.
+    pub fn rebase(&mut self, offset: Vec3) {
+        self.focus += offset;
+        self.current_focus += offset;
+    }
+}
+
+/// Player preferences for how the camera behaves.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraSettings {
+    /// Whether a run starts with the camera locked onto the player.
+    pub start_locked: bool,
+    pub edge_scroll: bool,
+    /// Multiplies edge scrolling speed.
+    pub pan_speed: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self { start_locked: true, edge_scroll: true, pan_speed: 1.0 }
+    }
+}
+
+pub struct CameraRigPlugin;
+
+impl Plugin for CameraRigPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<CameraRig>()
+            .init_resource::<CameraSettings>()
+            .add_systems(OnEnter(GameState::Playing), reset_camera_rig)
+            .add_systems(Update, (
+                camera_controls,
+                move_camera,
+            ).chain().run_if(in_state(PlayState::Running)));
+    }
+}
+
+fn reset_camera_rig(mut rig: ResMut<CameraRig>, settings: Res<CameraSettings>) {
+    *rig = CameraRig { locked: settings.start_locked, ..default() };
+}
+
+/// Where the camera looks while locked onto the player.
+fn player_focus(progress: &Progress, player: Vec3) -> Vec3 {
+    Vec3::new(progress.min_x, player.y, player.z)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn camera_controls(
+    time: Res<Time>,
+    actions: Actions,
+    mut wheel_events: EventReader<MouseWheel>,
+    mut motion_events: EventReader<MouseMotion>,
+    progress: Res<Progress>,
+    settings: Res<CameraSettings>,
+    mut rig: ResMut<CameraRig>,
+    window_query: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let player_focus = player_focus(&progress, player_transform.translation);
+
+    for event in wheel_events.read() {
+        // Pixel deltas (trackpads) are much finer than wheel lines
+        let lines = match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 40.0,
+        };
+        rig.zoom = (rig.zoom - lines * ZOOM_STEP).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+
+    if actions.just_pressed(Action::CameraLock) {
+        rig.locked = !rig.locked;
+        rig.focus = player_focus;
+    }
+    if actions.pressed(Action::CameraCenter) || rig.locked {
+        rig.focus = player_focus;
+        motion_events.clear();
+        return;
+    }
+
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+
+    // Dragging keeps the grabbed ground point under the cursor
+    let drag: Vec2 = motion_events.read().map(|event| event.delta).sum();
+    if let (true, Some(cursor), Ok((camera, camera_transform))) =
+        (actions.pressed(Action::CameraDrag), window.cursor_position(), camera_query.get_single())
+    {
+        let ground = |pos: Vec2| {
+            camera.viewport_to_world(camera_transform, window_to_viewport(camera, window, pos)).ok().and_then(|ray| {
+                let t = -ray.origin.y / ray.direction.y;
+                (t > 0.0).then(|| ray.get_point(t))
+            })
+        };
+        if let (Some(from), Some(to)) = (ground(cursor - drag), ground(cursor)) {
+            rig.focus += from - to;
+        }
+    } else if let (true, Some(cursor)) = (settings.edge_scroll, window.cursor_position()) {
+        // Screen right and up along the ground, for the fixed camera angle
+        let right = Vec3::new(1.0, 0.0, -1.0).normalize();
+        let up = Vec3::new(-1.0, 0.0, -1.0).normalize();
+        let size = window.size();
+        let mut dir = Vec3::ZERO;
+        if cursor.x < EDGE_SCROLL_MARGIN {
+            dir -= right;
+        }
+        if cursor.x > size.x - EDGE_SCROLL_MARGIN {
+            dir += right;
+        }
+        if cursor.y < EDGE_SCROLL_MARGIN {
+            dir += up;
+        }
+        if cursor.y > size.y - EDGE_SCROLL_MARGIN {
+            dir -= up;
+        }
+        let speed = EDGE_SCROLL_SPEED * settings.pan_speed * rig.zoom;
+        rig.focus += dir.normalize_or_zero() * speed * time.delta_secs();
+    }
+
+    rig.focus.x = rig.focus.x.clamp(progress.min_x - MAX_PAN_AHEAD, progress.wall_x);
+    rig.focus.z = rig.focus.z.clamp(-MAX_PAN_SIDE, MAX_PAN_SIDE);
+}
+
+pub fn move_camera(
+    time: Res<Time>,
+    mut rig: ResMut<CameraRig>,
+    mut camera_query: Query<&mut Transform, With<Camera3d>>,
+) {
+    let Ok(mut camera_transform) = camera_query.get_single_mut() else {
+        return;
+    };
+
+    let blend = 1.0 - (-CAMERA_SMOOTHING * time.delta_secs()).exp();
+    rig.current_focus = rig.current_focus.lerp(rig.focus, blend);
+    rig.current_zoom = rig.current_zoom.lerp(rig.zoom, blend);
+
+    camera_transform.translation = rig.current_focus + CAMERA_OFFSET * rig.current_zoom;
+    camera_transform.look_at(rig.current_focus, Vec3::Y);
+}
@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+
+use crate::app::{GameState, PlayState, SurvivalTimer};
+
+/// Clock hour at the start and end of a run; the run goes from morning into the night.
+const START_HOUR: f32 = 8.0;
+const END_HOUR: f32 = 26.0;
+const DAY_ILLUMINANCE: f32 = 12000.0;
+const NIGHT_ILLUMINANCE: f32 = 400.0;
+const DAY_AMBIENT_BRIGHTNESS: f32 = 800.0;
+const NIGHT_AMBIENT_BRIGHTNESS: f32 = 150.0;
+const STREET_LAMP_INTENSITY: f32 = 300_000.0;
+/// Enemies spawned at night have this much more health.
+pub const NIGHT_ENEMY_HEALTH_MULTIPLIER: f32 = 1.6;
+pub const NIGHT_ENEMY_SCALE: f32 = 1.15;
+
+/// Time of day, driven by the survival timer.
+#[derive(Resource)]
+pub struct TimeOfDay {
+    pub hour: f32,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        Self { hour: START_HOUR }
+    }
+}
+
+impl TimeOfDay {
+    /// Sun height: 1 at noon, 0 from sunset to sunrise.
+    pub fn daylight(&self) -> f32 {
+        (std::f32::consts::PI * (self.hour - 6.0) / 12.0).sin().max(0.0)
+    }
+
+    /// 0 by day, fading to 1 over dusk.
+    pub fn night(&self) -> f32 {
+        ((self.hour - 18.0) / 2.0).clamp(0.0, 1.0)
+    }
+
+    pub fn is_night(&self) -> bool {
+        self.night() >= 1.0
+    }
+
+    /// Colour the sun adds: orange near the horizon, white at noon, blue moonlight at night.
+    pub fn sun_tint(&self) -> Color {
+        let day = Color::WHITE.mix(&Color::srgb(1.0, 0.6, 0.35), 1.0 - self.daylight());
+        day.mix(&Color::srgb(0.45, 0.55, 0.9), self.night())
+    }
+
+    pub fn ambient_tint(&self) -> Color {
+        Color::WHITE.mix(&Color::srgb(0.35, 0.4, 0.7), self.night())
+    }
+}
+
+/// Multiplies `base` by `tint` channel by channel.
+pub fn tinted(base: Color, tint: Color) -> Color {
+    let (base, tint) = (base.to_linear(), tint.to_linear());
+    Color::linear_rgba(base.red * tint.red, base.green * tint.green, base.blue * tint.blue, base.alpha)
+}
+
+/// Point light on a street lamp that comes on after dusk.
+#[derive(Component)]
+pub struct StreetLamp;
+
+impl StreetLamp {
+    /// Lamp bundle, parented to a lamp post model.
+    pub fn bundle() -> impl Bundle {
+        (
+            StreetLamp,
+            PointLight {
+                color: Color::srgb(1.0, 0.9, 0.7),
+                intensity: 0.0,
+                range: 14.0,
+                ..default()
+            },
+            // Model space, so this is the lamp head
+            Transform::from_xyz(0.0, 0.55, -0.15),
+        )
+    }
+}
+
+pub struct DayNightPlugin;
+
+impl Plugin for DayNightPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<TimeOfDay>()
+            .add_systems(OnEnter(GameState::Playing), reset_time_of_day)
+            .add_systems(Update, (
+                update_time_of_day,
+                update_sun,
+                update_street_lamps,
+            ).chain().run_if(in_state(PlayState::Running)));
+    }
+}
+
+fn reset_time_of_day(mut time_of_day: ResMut<TimeOfDay>) {
+    *time_of_day = TimeOfDay::default();
+}
+
+fn update_time_of_day(timer: Option<Res<SurvivalTimer>>, mut time_of_day: ResMut<TimeOfDay>) {
+    if let Some(timer) = timer {
+        time_of_day.hour = START_HOUR + (END_HOUR - START_HOUR) * timer.0.fraction();
+    }
+}
+
+/// Swings the sun across the sky and dims the world at night. Colours are
+/// left to the biome blend, which applies the time-of-day tints.
+fn update_sun(
+    time_of_day: Res<TimeOfDay>,
+    mut ambient: ResMut<AmbientLight>,
+    mut sun_query: Query<(&mut DirectionalLight, &mut Transform)>,
+) {
+    let daylight = time_of_day.daylight();
+    let night = time_of_day.night();
+
+    // Full day ambient until dusk, so the morning start looks as it always has
+    ambient.brightness = DAY_AMBIENT_BRIGHTNESS.lerp(NIGHT_AMBIENT_BRIGHTNESS, night);
+    for (mut light, mut transform) in &mut sun_query {
+        light.illuminance = NIGHT_ILLUMINANCE.lerp(DAY_ILLUMINANCE, daylight);
+
+        // At night the moon hangs at a fixed angle
+        let elevation = if night < 1.0 { daylight.max(0.1) * std::f32::consts::FRAC_PI_2 } else { std::f32::consts::FRAC_PI_4 };
+        let azimuth = (time_of_day.hour - 12.0) / 12.0 * std::f32::consts::PI;
+        *transform = Transform::from_rotation(Quat::from_euler(EulerRot::YXZ, azimuth, -elevation, 0.0));
+    }
+}
+
+fn update_street_lamps(time_of_day: Res<TimeOfDay>, mut lamp_query: Query<&mut PointLight, With<StreetLamp>>) {
+    let intensity = STREET_LAMP_INTENSITY * time_of_day.night();
+    for mut light in &mut lamp_query {
+        light.intensity = intensity;
+    }
+}
@@ -0,0 +1,306 @@
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::ui::FocusPolicy;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{DamageEvent, DamageKind, Enemy, GameState, Health, PlayState, Player, PlayingUI, CHARACTER_RADIUS};
+use crate::camera::move_camera;
+use crate::lane::LaneLayout;
+
+/// Trauma lost per second; shake strength goes with trauma squared.
+const TRAUMA_DECAY: f32 = 1.2;
+/// Damage to the player that adds a full unit of trauma.
+const FULL_TRAUMA_DAMAGE: f32 = 50.0;
+const KILL_TRAUMA: f32 = 0.2;
+const SHAKE_FREQUENCY: f32 = 25.0;
+/// Game speed during a hit-stop.
+const HIT_STOP_SPEED: f32 = 0.05;
+const FLASH_SECS: f32 = 0.08;
+/// Damage that gives a knockback of `knockback_strength`.
+const KNOCKBACK_REFERENCE_DAMAGE: f32 = 25.0;
+const KNOCKBACK_DAMPING: f32 = 12.0;
+const VIGNETTE_TEXTURE_SIZE: u32 = 128;
+
+/// Tuning for impact feedback. Each effect can be switched off on its own,
+/// e.g. shake and flashes for players sensitive to motion or flicker.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameFeelSettings {
+    pub camera_shake: bool,
+    /// Camera offset at full trauma.
+    pub shake_strength: f32,
+    pub hit_stop: bool,
+    /// Hits at least this strong freeze the game briefly.
+    pub hit_stop_damage: f32,
+    /// Real seconds a hit-stop lasts.
+    pub hit_stop_secs: f32,
+    pub hit_flash: bool,
+    pub knockback: bool,
+    /// Initial knockback speed for a reference-sized hit.
+    pub knockback_strength: f32,
+    pub low_health_vignette: bool,
+    /// Health fraction below which the vignette fades in.
+    pub vignette_threshold: f32,
+}
+
+impl Default for GameFeelSettings {
+    fn default() -> Self {
+        Self {
+            camera_shake: true,
+            shake_strength: 0.8,
+            hit_stop: true,
+            hit_stop_damage: 30.0,
+            hit_stop_secs: 0.06,
+            hit_flash: true,
+            knockback: true,
+            knockback_strength: 6.0,
+            low_health_vignette: true,
+            vignette_threshold: 0.35,
+        }
+    }
+}
+
+/// Camera shake budget, 0 to 1.
+#[derive(Resource, Default)]
+pub struct Trauma(pub f32);
+
+/// Real seconds left in the current hit-stop.
+#[derive(Resource, Default)]
+pub struct HitStop(pub f32);
+
+/// A model mesh showing the hit flash; restores its material when done.
+#[derive(Component)]
+pub struct HitFlash {
+    timer: Timer,
+    original: Handle<StandardMaterial>,
+}
+
+/// Sliding velocity from being hit, damped to a stop.
+#[derive(Component)]
+pub struct Knockback(pub Vec3);
+
+#[derive(Component)]
+pub struct LowHealthVignette;
+
+#[derive(Resource)]
+pub struct FeelAssets {
+    flash_material: Handle<StandardMaterial>,
+}
+
+pub struct GameFeelPlugin;
+
+impl Plugin for GameFeelPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GameFeelSettings>()
+            .init_resource::<Trauma>()
+            .init_resource::<HitStop>()
+            .add_systems(OnEnter(GameState::Playing), setup_feel)
+            .add_systems(Update, (
+                react_to_damage,
+                update_hit_stop,
+                update_hit_flashes,
+                apply_knockback,
+                update_vignette,
+            ).chain().run_if(in_state(PlayState::Running)))
+            .add_systems(Update, shake_camera.after(move_camera).run_if(in_state(PlayState::Running)))
+            .add_systems(OnExit(GameState::Playing), reset_feel);
+    }
+}
+
+fn setup_feel(
+    mut commands: Commands,
+    mut trauma: ResMut<Trauma>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    trauma.0 = 0.0;
+    commands.insert_resource(FeelAssets {
+        flash_material: materials.add(StandardMaterial {
+            base_color: Color::WHITE,
+            emissive: LinearRgba::new(4.0, 4.0, 4.0, 1.0),
+            unlit: true,
+            ..default()
+        }),
+    });
+
+    // Red towards the edges, clear in the middle
+    let mut image = Image::new_fill(
+        Extent3d { width: VIGNETTE_TEXTURE_SIZE, height: VIGNETTE_TEXTURE_SIZE, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    let center = Vec2::splat(VIGNETTE_TEXTURE_SIZE as f32 / 2.0);
+    for (i, texel) in image.data.chunks_exact_mut(4).enumerate() {
+        let pos = Vec2::new((i as u32 % VIGNETTE_TEXTURE_SIZE) as f32, (i as u32 / VIGNETTE_TEXTURE_SIZE) as f32) + 0.5;
+        let edge = ((pos.distance(center) / center.x - 0.55) / 0.6).clamp(0.0, 1.0);
+        texel.copy_from_slice(&[200, 0, 0, (edge * edge * 255.0) as u8]);
+    }
+
+    commands.spawn((
+        LowHealthVignette,
+        ImageNode::new(images.add(image)).with_color(Color::NONE),
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        // Purely visual: under the HUD and never eats clicks
+        ZIndex(-1),
+        FocusPolicy::Pass,
+        PlayingUI,
+    ));
+}
+
+fn reset_feel(mut time: ResMut<Time<Virtual>>, mut hit_stop: ResMut<HitStop>) {
+    hit_stop.0 = 0.0;
+    time.set_relative_speed(1.0);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn react_to_damage(
+    mut commands: Commands,
+    settings: Res<GameFeelSettings>,
+    feel_assets: Res<FeelAssets>,
+    mut trauma: ResMut<Trauma>,
+    mut hit_stop: ResMut<HitStop>,
+    mut damage_events: EventReader<DamageEvent>,
+    player_query: Query<(), With<Player>>,
+    children_query: Query<&Children>,
+    scene_query: Query<(), With<SceneRoot>>,
+    mut mesh_query: Query<(&mut MeshMaterial3d<StandardMaterial>, Option<&mut HitFlash>)>,
+) {
+    for event in damage_events.read() {
+        let is_player = player_query.contains(event.target);
+        if is_player {
+            trauma.0 += event.amount / FULL_TRAUMA_DAMAGE;
+        } else if event.fatal {
+            trauma.0 += KILL_TRAUMA;
+        }
+        trauma.0 = trauma.0.min(1.0);
+
+        if settings.hit_stop && event.amount >= settings.hit_stop_damage {
+            hit_stop.0 = hit_stop.0.max(settings.hit_stop_secs);
+        }
+
+        if settings.knockback && event.direction != Vec3::ZERO {
+            let push = event.direction.with_y(0.0).normalize_or_zero()
+                * settings.knockback_strength
+                * (event.amount / KNOCKBACK_REFERENCE_DAMAGE).min(2.0);
+            if let Some(mut target) = commands.get_entity(event.target) {
+                target.try_insert(Knockback(push));
+            }
+        }
+
+        // Damage-over-time ticks every frame, so it doesn't flash
+        if !settings.hit_flash || event.kind == DamageKind::OverTime {
+            continue;
+        }
+        // Only the model's meshes flash
+        let Ok(children) = children_query.get(event.target) else {
+            continue;
+        };
+        for scene in children.iter().filter(|child| scene_query.contains(**child)) {
+            for mesh in children_query.iter_descendants(*scene) {
+                let Ok((mut material, flash)) = mesh_query.get_mut(mesh) else {
+                    continue;
+                };
+                match flash {
+                    Some(mut flash) => flash.timer.reset(),
+                    None => {
+                        let original = std::mem::replace(&mut material.0, feel_assets.flash_material.clone());
+                        // The target may be despawned by its death this frame
+                        commands.entity(mesh).try_insert(HitFlash {
+                            timer: Timer::from_seconds(FLASH_SECS, TimerMode::Once),
+                            original,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Slows the game right down for a moment, timed in real seconds.
+fn update_hit_stop(real_time: Res<Time<Real>>, mut time: ResMut<Time<Virtual>>, mut hit_stop: ResMut<HitStop>) {
+    if hit_stop.0 > 0.0 {
+        hit_stop.0 -= real_time.delta_secs();
+        time.set_relative_speed(HIT_STOP_SPEED);
+    } else if time.relative_speed() != 1.0 {
+        time.set_relative_speed(1.0);
+    }
+}
+
+fn update_hit_flashes(
+    mut commands: Commands,
+    real_time: Res<Time<Real>>,
+    mut flash_query: Query<(Entity, &mut HitFlash, &mut MeshMaterial3d<StandardMaterial>)>,
+) {
+    for (entity, mut flash, mut material) in &mut flash_query {
+        if flash.timer.tick(real_time.delta()).finished() {
+            material.0 = flash.original.clone();
+            commands.entity(entity).remove::<HitFlash>();
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn apply_knockback(
+    mut commands: Commands,
+    time: Res<Time>,
+    layout: Res<LaneLayout>,
+    mut knockback_query: Query<(Entity, &mut Transform, &mut Knockback), Or<(With<Player>, With<Enemy>)>>,
+) {
+    for (entity, mut transform, mut knockback) in &mut knockback_query {
+        let pos = transform.translation + knockback.0 * time.delta_secs();
+        transform.translation = layout.resolve_collision(pos, CHARACTER_RADIUS);
+        knockback.0 *= (-KNOCKBACK_DAMPING * time.delta_secs()).exp();
+        if knockback.0.length_squared() < 0.01 {
+            commands.entity(entity).remove::<Knockback>();
+        }
+    }
+}
+
+fn update_vignette(
+    time: Res<Time<Real>>,
+    settings: Res<GameFeelSettings>,
+    player_query: Query<&Health, With<Player>>,
+    mut vignette_query: Query<&mut ImageNode, With<LowHealthVignette>>,
+) {
+    let fraction = player_query.get_single().map_or(1.0, |health| (health.current / health.max).max(0.0));
+    let intensity = if settings.low_health_vignette {
+        ((settings.vignette_threshold - fraction) / settings.vignette_threshold).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    // A slow heartbeat pulse
+    let pulse = 0.85 + 0.15 * (time.elapsed_secs() * 5.0).sin();
+    for mut image in &mut vignette_query {
+        image.color = Color::srgba(1.0, 1.0, 1.0, intensity * pulse);
+    }
+}
+
+/// Offsets the camera after the rig has placed it; runs on real time so it
+/// keeps going through hit-stops.
+fn shake_camera(
+    time: Res<Time<Real>>,
+    settings: Res<GameFeelSettings>,
+    mut trauma: ResMut<Trauma>,
+    mut camera_query: Query<&mut Transform, With<Camera3d>>,
+) {
+    trauma.0 = (trauma.0 - TRAUMA_DECAY * time.delta_secs()).max(0.0);
+    if !settings.camera_shake || trauma.0 <= 0.0 {
+        return;
+    }
+
+    let t = time.elapsed_secs() * SHAKE_FREQUENCY;
+    let noise = Vec3::new((t * 1.0).sin() * (t * 0.37).cos(), (t * 1.31).sin() * (t * 0.53).cos(), (t * 0.89).sin() * (t * 0.71).cos());
+    let offset = noise * settings.shake_strength * trauma.0 * trauma.0;
+    for mut transform in &mut camera_query {
+        transform.translation += offset;
+    }
+}
@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages};
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+
+use crate::app::GameState;
+
+/// Graphics quality options.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GraphicsSettings {
+    /// Whether the sun casts shadows.
+    pub shadows: bool,
+    /// Fraction of the window's resolution the world is rendered at, up to 1.
+    pub render_scale: f32,
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        Self { shadows: true, render_scale: 1.0 }
+    }
+}
+
+/// Image the 3D camera renders into below full render scale, stretched over
+/// the window underneath the UI.
+#[derive(Resource, Default)]
+pub struct ScaledView(Option<Handle<Image>>);
+
+/// The window camera and full-screen image showing a `ScaledView`.
+#[derive(Component)]
+pub struct ScaledViewOutput;
+
+pub struct GraphicsPlugin;
+
+impl Plugin for GraphicsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GraphicsSettings>()
+            .init_resource::<ScaledView>()
+            .add_systems(Update, apply_shadows)
+            // Also while paused, so changes show behind the settings screen
+            .add_systems(Update, apply_render_scale.run_if(in_state(GameState::Playing)))
+            .add_systems(OnExit(GameState::Playing), cleanup_scaled_view);
+    }
+}
+
+/// Maps a window position to the 3D camera's viewport, which is smaller than
+/// the window below full render scale.
+pub fn window_to_viewport(camera: &Camera, window: &Window, pos: Vec2) -> Vec2 {
+    camera.logical_viewport_size().map_or(pos, |size| pos * size / window.size())
+}
+
+/// Maps a position in the 3D camera's viewport back to the window.
+pub fn viewport_to_window(camera: &Camera, window: &Window, pos: Vec2) -> Vec2 {
+    camera.logical_viewport_size().map_or(pos, |size| pos * window.size() / size)
+}
+
+fn apply_shadows(settings: Res<GraphicsSettings>, mut light_query: Query<&mut DirectionalLight>) {
+    for mut light in &mut light_query {
+        if light.shadows_enabled != settings.shadows {
+            light.shadows_enabled = settings.shadows;
+        }
+    }
+}
+
+fn apply_render_scale(
+    mut commands: Commands,
+    settings: Res<GraphicsSettings>,
+    mut view: ResMut<ScaledView>,
+    mut images: ResMut<Assets<Image>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut camera_query: Query<&mut Camera, With<Camera3d>>,
+    output_query: Query<Entity, With<ScaledViewOutput>>,
+) {
+    let (Ok(window), Ok(mut camera)) = (window_query.get_single(), camera_query.get_single_mut()) else {
+        return;
+    };
+
+    if settings.render_scale >= 1.0 {
+        if view.0.take().is_some() {
+            camera.target = RenderTarget::default();
+            for entity in &output_query {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+        return;
+    }
+
+    let size = (window.physical_size().as_vec2() * settings.render_scale).round().as_uvec2().max(UVec2::ONE);
+    let extent = Extent3d { width: size.x, height: size.y, depth_or_array_layers: 1 };
+    let handle = match &view.0 {
+        Some(handle) => {
+            // Follows window resizes and scale changes
+            if images.get(handle).is_some_and(|image| image.size() != size) {
+                if let Some(image) = images.get_mut(handle) {
+                    image.resize(extent);
+                }
+            }
+            handle.clone()
+        }
+        None => {
+            let mut image = Image::new_fill(
+                extent,
+                TextureDimension::D2,
+                &[0, 0, 0, 255],
+                TextureFormat::Bgra8UnormSrgb,
+                RenderAssetUsages::default(),
+            );
+            image.texture_descriptor.usage =
+                TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT;
+            let handle = images.add(image);
+
+            // Draws the image and, as the only camera on the window, the UI
+            commands.spawn((
+                Camera2d,
+                Camera { order: 1, ..default() },
+                ScaledViewOutput,
+            ));
+            commands.spawn((
+                ImageNode::new(handle.clone()),
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                GlobalZIndex(-1),
+                ScaledViewOutput,
+            ));
+            view.0 = Some(handle.clone());
+            handle
+        }
+    };
+
+    if camera.target.as_image() != Some(&handle) {
+        camera.target = RenderTarget::Image(handle);
+    }
+}
+
+fn cleanup_scaled_view(
+    mut commands: Commands,
+    mut view: ResMut<ScaledView>,
+    query: Query<Entity, With<ScaledViewOutput>>,
+) {
+    view.0 = None;
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,473 @@
+use bevy::prelude::*;
+use rand::Rng;
+use std::time::Duration;
+
+use crate::app::{DamageEvent, DamageKind, Dashing, Enemy, GameState, Health, Invulnerable, PlayState, Player, Progress, Respawning, LANE_HALF_WIDTH};
+use crate::lane::{LaneLayout, LaneSegmentId};
+
+const DEBRIS_INTERVAL_SECS: f32 = 3.0;
+/// How long the ground circle shows before the debris lands.
+const DEBRIS_TELEGRAPH_SECS: f32 = 1.5;
+const DEBRIS_DROP_HEIGHT: f32 = 14.0;
+/// Debris lands this far from the construction site at most.
+const DEBRIS_SCATTER: f32 = 6.0;
+const DEBRIS_RADIUS: f32 = 2.0;
+const DEBRIS_DAMAGE: f32 = 35.0;
+const TRAFFIC_INTERVAL_SECS: f32 = 9.0;
+const TRAFFIC_WARNING_SECS: f32 = 1.5;
+const CAR_SPEED: f32 = 30.0;
+/// Cars spawn and despawn this far from the lane centre.
+const CAR_TRAVEL: f32 = 40.0;
+const CAR_HALF_EXTENTS: Vec2 = Vec2::new(1.0, 2.0);
+const CAR_DAMAGE: f32 = 40.0;
+const PUDDLE_CYCLE_SECS: f32 = 4.0;
+/// Part of the cycle the puddle is live for.
+const PUDDLE_LIVE_SECS: f32 = 1.5;
+const PUDDLE_RADIUS: f32 = 1.8;
+const PUDDLE_DAMAGE_PER_SEC: f32 = 20.0;
+/// Depth of the damaging band in front of the wall.
+pub const STORM_DEPTH: f32 = 2.0;
+/// How far the visible storm reaches back behind the wall.
+const STORM_TAIL: f32 = 30.0;
+const STORM_DAMAGE_PER_SEC: f32 = 15.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HazardKind {
+    /// Construction site dropping debris onto telegraphed spots around it.
+    DebrisZone,
+    /// Cross street that a car periodically speeds along.
+    Traffic,
+    /// Puddle under a street light that is electrified part of the time.
+    Puddle,
+}
+
+/// Hazard spawner placed by the lane generator.
+#[derive(Component)]
+pub struct Hazard {
+    pub kind: HazardKind,
+    pub timer: Timer,
+}
+
+impl Hazard {
+    pub fn new(kind: HazardKind) -> Self {
+        let secs = match kind {
+            HazardKind::DebrisZone => DEBRIS_INTERVAL_SECS,
+            HazardKind::Traffic => TRAFFIC_INTERVAL_SECS,
+            HazardKind::Puddle => PUDDLE_CYCLE_SECS,
+        };
+        // Stagger hazards of the same kind
+        let mut timer = Timer::from_seconds(secs, TimerMode::Repeating);
+        timer.set_elapsed(Duration::from_secs_f32(rand::thread_rng().gen_range(0.0..secs)));
+        Self { kind, timer }
+    }
+
+    /// Puddles are live at the end of each cycle.
+    pub fn is_live(&self) -> bool {
+        self.kind == HazardKind::Puddle && self.timer.remaining_secs() < PUDDLE_LIVE_SECS
+    }
+}
+
+/// Debris block on its way down to the `telegraph` circle.
+#[derive(Component)]
+pub struct FallingDebris {
+    timer: Timer,
+    telegraph: Entity,
+}
+
+#[derive(Component)]
+pub struct DebrisTelegraph;
+
+/// Flashing stripe across the lane before a car comes through.
+#[derive(Component)]
+pub struct TrafficWarning {
+    timer: Timer,
+    direction: f32,
+}
+
+#[derive(Component)]
+pub struct Car {
+    velocity: Vec3,
+    /// Everyone already hit, so a pass only hurts once.
+    hit: Vec<Entity>,
+}
+
+/// Damaging zone in front of `Progress.wall_x`.
+#[derive(Component)]
+pub struct StormWall;
+
+#[derive(Resource)]
+pub struct HazardAssets {
+    decal_mesh: Handle<Mesh>,
+    telegraph_material: Handle<StandardMaterial>,
+    puddle_material: Handle<StandardMaterial>,
+    live_puddle_material: Handle<StandardMaterial>,
+    block_mesh: Handle<Mesh>,
+    block_material: Handle<StandardMaterial>,
+    stripe_mesh: Handle<Mesh>,
+    warning_material: Handle<StandardMaterial>,
+    car_body_mesh: Handle<Mesh>,
+    car_cabin_mesh: Handle<Mesh>,
+    car_materials: Vec<Handle<StandardMaterial>>,
+}
+
+pub(crate) type PlayerTargets<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static Transform, &'static mut Health),
+    (With<Player>, Without<Respawning>, Without<Invulnerable>, Without<Dashing>),
+>;
+pub(crate) type EnemyTargets<'w, 's> = Query<'w, 's, (Entity, &'static Transform, &'static mut Health), (With<Enemy>, Without<Player>)>;
+
+/// Damages the player and every enemy for which `hit` returns true.
+pub(crate) fn hurt(
+    players: &mut PlayerTargets,
+    enemies: &mut EnemyTargets,
+    events: &mut EventWriter<DamageEvent>,
+    kind: DamageKind,
+    damage: f32,
+    mut hit: impl FnMut(Entity, Vec3) -> bool,
+) {
+    for (entity, transform, mut health) in players.iter_mut().chain(enemies.iter_mut()) {
+        if hit(entity, transform.translation) {
+            let fatal = health.take(damage);
+            events.send(DamageEvent { target: entity, amount: damage, kind, position: transform.translation, direction: Vec3::ZERO, fatal });
+        }
+    }
+}
+
+pub struct HazardsPlugin;
+
+impl Plugin for HazardsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(GameState::Playing), setup_hazards)
+            .add_systems(Update, (
+                dress_hazards,
+                drop_debris,
+                update_falling_debris,
+                dispatch_traffic,
+                update_traffic_warnings,
+                update_cars,
+                update_puddles,
+                update_storm_wall,
+            ).run_if(in_state(PlayState::Running)))
+            .add_systems(OnExit(GameState::Playing), cleanup_hazards);
+    }
+}
+
+fn setup_hazards(
+    mut commands: Commands,
+    progress: Res<Progress>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let car_materials = [
+        Color::srgb(0.8, 0.15, 0.1),
+        Color::srgb(0.1, 0.3, 0.8),
+        Color::srgb(0.9, 0.75, 0.1),
+        Color::srgb(0.85, 0.85, 0.85),
+    ]
+    .into_iter()
+    .map(|color| materials.add(color))
+    .collect();
+
+    commands.insert_resource(HazardAssets {
+        decal_mesh: meshes.add(Circle::new(1.0).mesh().resolution(32)),
+        telegraph_material: materials.add(StandardMaterial {
+            base_color: Color::srgba(1.0, 0.2, 0.1, 0.4),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+        puddle_material: materials.add(StandardMaterial {
+            base_color: Color::srgba(0.2, 0.3, 0.45, 0.7),
+            alpha_mode: AlphaMode::Blend,
+            perceptual_roughness: 0.05,
+            ..default()
+        }),
+        live_puddle_material: materials.add(StandardMaterial {
+            base_color: Color::srgba(0.4, 0.9, 1.0, 0.8),
+            emissive: LinearRgba::new(1.0, 6.0, 10.0, 1.0),
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        }),
+        block_mesh: meshes.add(Cuboid::new(1.2, 1.2, 1.2)),
+        block_material: materials.add(Color::srgb(0.5, 0.45, 0.4)),
+        stripe_mesh: meshes.add(Plane3d::default().mesh().size(1.0, 1.0)),
+        warning_material: materials.add(StandardMaterial {
+            base_color: Color::srgba(1.0, 0.8, 0.0, 0.5),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+        car_body_mesh: meshes.add(Cuboid::new(2.0 * CAR_HALF_EXTENTS.x, 0.8, 2.0 * CAR_HALF_EXTENTS.y)),
+        car_cabin_mesh: meshes.add(Cuboid::new(1.6, 0.6, 2.0)),
+        car_materials,
+    });
+
+    // The wall is a creeping storm covering everything behind it
+    commands.spawn((
+        StormWall,
+        Mesh3d(meshes.add(Cuboid::new(1.0, 1.0, 1.0))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::srgba(0.45, 0.1, 0.7, 0.35),
+            emissive: LinearRgba::new(1.5, 0.3, 3.0, 1.0),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        })),
+        Transform::from_xyz(progress.wall_x, 6.0, 0.0).with_scale(Vec3::new(STORM_DEPTH + STORM_TAIL, 12.0, 4.0 * LANE_HALF_WIDTH)),
+        Visibility::Visible,
+        InheritedVisibility::default(),
+    ));
+}
+
+#[allow(clippy::type_complexity)]
+fn cleanup_hazards(
+    mut commands: Commands,
+    query: Query<Entity, Or<(With<Hazard>, With<FallingDebris>, With<DebrisTelegraph>, With<TrafficWarning>, With<Car>, With<StormWall>)>>,
+) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<HazardAssets>();
+}
+
+/// Gives newly streamed-in puddles their decal.
+fn dress_hazards(
+    mut commands: Commands,
+    hazard_assets: Res<HazardAssets>,
+    mut hazard_query: Query<(Entity, &Hazard, &mut Transform), Added<Hazard>>,
+) {
+    for (entity, hazard, mut transform) in &mut hazard_query {
+        if hazard.kind == HazardKind::Puddle {
+            transform.translation.y = 0.05;
+            transform.rotation = Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2);
+            transform.scale = Vec3::splat(PUDDLE_RADIUS);
+            commands.entity(entity).insert((
+                Mesh3d(hazard_assets.decal_mesh.clone()),
+                MeshMaterial3d(hazard_assets.puddle_material.clone()),
+            ));
+        }
+    }
+}
+
+fn drop_debris(
+    mut commands: Commands,
+    time: Res<Time>,
+    hazard_assets: Res<HazardAssets>,
+    layout: Res<LaneLayout>,
+    mut hazard_query: Query<(&Transform, &mut Hazard)>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (transform, mut hazard) in &mut hazard_query {
+        if hazard.kind != HazardKind::DebrisZone || !hazard.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let offset = Vec3::new(rng.gen_range(-DEBRIS_SCATTER..DEBRIS_SCATTER), 0.0, rng.gen_range(-DEBRIS_SCATTER..DEBRIS_SCATTER));
+        let mut target = transform.translation + offset;
+        target.z = target.z.clamp(-LANE_HALF_WIDTH, LANE_HALF_WIDTH);
+        let segment_id = layout.index_at(target.x);
+
+        let telegraph = commands.spawn((
+            DebrisTelegraph,
+            LaneSegmentId(segment_id),
+            Mesh3d(hazard_assets.decal_mesh.clone()),
+            MeshMaterial3d(hazard_assets.telegraph_material.clone()),
+            Transform::from_translation(target + Vec3::Y * 0.06)
+                .with_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2))
+                .with_scale(Vec3::ZERO),
+            Visibility::Visible,
+            InheritedVisibility::default(),
+        )).id();
+        commands.spawn((
+            FallingDebris {
+                timer: Timer::from_seconds(DEBRIS_TELEGRAPH_SECS, TimerMode::Once),
+                telegraph,
+            },
+            LaneSegmentId(segment_id),
+            Mesh3d(hazard_assets.block_mesh.clone()),
+            MeshMaterial3d(hazard_assets.block_material.clone()),
+            Transform::from_translation(target + Vec3::Y * DEBRIS_DROP_HEIGHT)
+                .with_rotation(Quat::from_rotation_y(rng.gen_range(0.0..std::f32::consts::TAU))),
+            Visibility::Visible,
+            InheritedVisibility::default(),
+        ));
+    }
+}
+
+/// The circle fills in while the block falls; landing hurts everything inside it.
+#[allow(clippy::type_complexity)]
+fn update_falling_debris(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut debris_query: Query<(Entity, &mut Transform, &mut FallingDebris), (Without<Player>, Without<Enemy>)>,
+    mut telegraph_query: Query<&mut Transform, (With<DebrisTelegraph>, Without<FallingDebris>, Without<Player>, Without<Enemy>)>,
+    mut players: PlayerTargets,
+    mut enemies: EnemyTargets,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    for (entity, mut transform, mut debris) in &mut debris_query {
+        debris.timer.tick(time.delta());
+        let t = debris.timer.fraction();
+
+        let Ok(mut telegraph) = telegraph_query.get_mut(debris.telegraph) else {
+            continue;
+        };
+        telegraph.scale = Vec3::splat(DEBRIS_RADIUS * t);
+        // Hang at the top, then accelerate down
+        transform.translation.y = DEBRIS_DROP_HEIGHT * (1.0 - t * t) + 0.6;
+
+        if debris.timer.finished() {
+            let center = telegraph.translation.xz();
+            hurt(&mut players, &mut enemies, &mut damage_events, DamageKind::Hit, DEBRIS_DAMAGE, |_, pos| pos.xz().distance(center) < DEBRIS_RADIUS);
+            commands.entity(debris.telegraph).despawn();
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn dispatch_traffic(
+    mut commands: Commands,
+    time: Res<Time>,
+    hazard_assets: Res<HazardAssets>,
+    mut hazard_query: Query<(&Transform, &mut Hazard, &LaneSegmentId)>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (transform, mut hazard, segment_id) in &mut hazard_query {
+        if hazard.kind != HazardKind::Traffic || !hazard.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        commands.spawn((
+            TrafficWarning {
+                timer: Timer::from_seconds(TRAFFIC_WARNING_SECS, TimerMode::Once),
+                direction: if rng.gen_bool(0.5) { 1.0 } else { -1.0 },
+            },
+            LaneSegmentId(segment_id.0),
+            Mesh3d(hazard_assets.stripe_mesh.clone()),
+            MeshMaterial3d(hazard_assets.warning_material.clone()),
+            Transform::from_translation(transform.translation + Vec3::Y * 0.06)
+                .with_scale(Vec3::new(2.0 * CAR_HALF_EXTENTS.x + 1.0, 1.0, 2.0 * LANE_HALF_WIDTH)),
+            Visibility::Visible,
+            InheritedVisibility::default(),
+        ));
+    }
+}
+
+/// The stripe blinks faster as the car gets closer, then the car comes through.
+fn update_traffic_warnings(
+    mut commands: Commands,
+    time: Res<Time>,
+    hazard_assets: Res<HazardAssets>,
+    mut warning_query: Query<(Entity, &Transform, &mut TrafficWarning, &LaneSegmentId, &mut Visibility)>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (entity, transform, mut warning, segment_id, mut visibility) in &mut warning_query {
+        warning.timer.tick(time.delta());
+        let blink = 4.0 + 12.0 * warning.timer.fraction();
+        *visibility = if (warning.timer.elapsed_secs() * blink).fract() < 0.5 { Visibility::Visible } else { Visibility::Hidden };
+
+        if !warning.timer.finished() {
+            continue;
+        }
+        commands.entity(entity).despawn();
+
+        let material = hazard_assets.car_materials[rng.gen_range(0..hazard_assets.car_materials.len())].clone();
+        let start = Vec3::new(transform.translation.x, 0.6, -warning.direction * CAR_TRAVEL);
+        commands.spawn((
+            Car { velocity: Vec3::Z * warning.direction * CAR_SPEED, hit: Vec::new() },
+            LaneSegmentId(segment_id.0),
+            Mesh3d(hazard_assets.car_body_mesh.clone()),
+            MeshMaterial3d(material.clone()),
+            Transform::from_translation(start),
+            Visibility::Visible,
+            InheritedVisibility::default(),
+        )).with_child((
+            Mesh3d(hazard_assets.car_cabin_mesh.clone()),
+            MeshMaterial3d(material),
+            Transform::from_xyz(0.0, 0.7, 0.0),
+        ));
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_cars(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut car_query: Query<(Entity, &mut Transform, &mut Car), (Without<Player>, Without<Enemy>)>,
+    mut players: PlayerTargets,
+    mut enemies: EnemyTargets,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    for (entity, mut transform, mut car) in &mut car_query {
+        transform.translation += car.velocity * time.delta_secs();
+        if transform.translation.z.abs() > CAR_TRAVEL {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let center = transform.translation.xz();
+        let reach = CAR_HALF_EXTENTS + Vec2::splat(0.6);
+        let hit = &mut car.hit;
+        hurt(&mut players, &mut enemies, &mut damage_events, DamageKind::Hit, CAR_DAMAGE, |target, pos| {
+            let delta = (pos.xz() - center).abs();
+            if delta.x > reach.x || delta.y > reach.y || hit.contains(&target) {
+                return false;
+            }
+            hit.push(target);
+            true
+        });
+    }
+}
+
+fn update_puddles(
+    time: Res<Time>,
+    hazard_assets: Res<HazardAssets>,
+    mut hazard_query: Query<(&Transform, &mut Hazard, &mut MeshMaterial3d<StandardMaterial>)>,
+    mut players: PlayerTargets,
+    mut enemies: EnemyTargets,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    let damage = PUDDLE_DAMAGE_PER_SEC * time.delta_secs();
+
+    for (transform, mut hazard, mut material) in &mut hazard_query {
+        if hazard.kind != HazardKind::Puddle {
+            continue;
+        }
+        hazard.timer.tick(time.delta());
+
+        let live = hazard.is_live();
+        let wanted = if live { &hazard_assets.live_puddle_material } else { &hazard_assets.puddle_material };
+        if material.0 != *wanted {
+            material.0 = wanted.clone();
+        }
+        if live {
+            let center = transform.translation.xz();
+            hurt(&mut players, &mut enemies, &mut damage_events, DamageKind::OverTime, damage, |_, pos| pos.xz().distance(center) < PUDDLE_RADIUS);
+        }
+    }
+}
+
+/// Keeps the storm on the wall and hurts anyone standing in it.
+#[allow(clippy::type_complexity)]
+fn update_storm_wall(
+    time: Res<Time>,
+    progress: Res<Progress>,
+    mut wall_query: Query<&mut Transform, (With<StormWall>, Without<Player>, Without<Enemy>)>,
+    mut players: PlayerTargets,
+    mut enemies: EnemyTargets,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    // Its front face sits at the edge of the damage band and it churns a little
+    let churn = (time.elapsed_secs() * 3.0).sin() * 0.3;
+    for mut transform in &mut wall_query {
+        transform.translation.x = progress.wall_x - STORM_DEPTH + (STORM_DEPTH + STORM_TAIL) / 2.0 + churn;
+    }
+
+    let edge = progress.wall_x - STORM_DEPTH;
+    hurt(&mut players, &mut enemies, &mut damage_events, DamageKind::OverTime, STORM_DAMAGE_PER_SEC * time.delta_secs(), |_, pos| pos.x > edge);
+}
@@ -0,0 +1,294 @@
+use bevy::prelude::*;
+
+use crate::app::{GameState, Health, Level, PlayState, Player, Respawning};
+use crate::numbers::number_glyph_paths;
+
+const BAR_SIZE: Vec2 = Vec2::new(2.0, 0.2);
+/// Border of the background around the bar.
+const BAR_BORDER: f32 = 0.04;
+/// Height above the target's feet.
+const BAR_HEIGHT: f32 = 3.5;
+/// The damage chunk holds for this long after a hit, then drains away.
+const LAG_DELAY_SECS: f32 = 0.4;
+/// Fraction of the bar per second.
+const LAG_DRAIN_RATE: f32 = 0.8;
+const LEVEL_DIGIT_SIZE: f32 = 0.45;
+
+/// Shared mesh and materials, so bars cost no allocations per combatant.
+#[derive(Resource)]
+pub struct HealthBarAssets {
+    quad: Handle<Mesh>,
+    background: Handle<StandardMaterial>,
+    player_fill: Handle<StandardMaterial>,
+    enemy_fill: Handle<StandardMaterial>,
+    lag: Handle<StandardMaterial>,
+    shield: Handle<StandardMaterial>,
+    digits: Vec<Handle<StandardMaterial>>,
+}
+
+/// Bars whose target is gone, hidden and waiting to be reused.
+#[derive(Resource, Default)]
+pub struct HealthBarPool(Vec<Entity>);
+
+/// Camera-facing bar floating over `target`. Not parented to it, so it
+/// doesn't turn when the target does.
+#[derive(Component)]
+pub struct HealthBar {
+    target: Option<Entity>,
+    /// Health fraction the damage chunk currently reaches.
+    lag: f32,
+    /// Health fraction last frame, to spot fresh damage.
+    last: f32,
+    lag_delay: f32,
+}
+
+impl HealthBar {
+    fn attach(&mut self, target: Entity) {
+        *self = Self { target: Some(target), lag: 1.0, last: 1.0, lag_delay: 0.0 };
+    }
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum HealthBarLayer {
+    Lag,
+    Fill,
+    Shield,
+}
+
+impl HealthBarLayer {
+    /// Offset toward the camera, so later layers draw on top.
+    fn depth(self) -> f32 {
+        match self {
+            HealthBarLayer::Lag => 0.002,
+            HealthBarLayer::Fill => 0.004,
+            HealthBarLayer::Shield => 0.006,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct LevelDigit;
+
+pub struct HealthBarPlugin;
+
+impl Plugin for HealthBarPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<HealthBarPool>()
+            .add_systems(OnEnter(GameState::Playing), setup_health_bar_assets)
+            .add_systems(Update, (
+                attach_health_bars,
+                update_health_bars,
+            ).chain().run_if(in_state(PlayState::Running)))
+            .add_systems(OnExit(GameState::Playing), cleanup_health_bars);
+    }
+}
+
+fn setup_health_bar_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut flat = |color: Color| {
+        materials.add(StandardMaterial {
+            base_color: color,
+            unlit: true,
+            ..default()
+        })
+    };
+    let background = flat(Color::srgb(0.2, 0.0, 0.0));
+    let player_fill = flat(Color::srgb(0.0, 1.0, 0.0));
+    let enemy_fill = flat(Color::srgb(1.0, 0.0, 0.0));
+    let lag = flat(Color::srgb(1.0, 0.9, 0.6));
+    let shield = flat(Color::srgb(0.75, 0.85, 1.0));
+    let digits = number_glyph_paths()
+        .into_iter()
+        .take(10)
+        .map(|path| {
+            materials.add(StandardMaterial {
+                base_color_texture: Some(asset_server.load(path)),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            })
+        })
+        .collect();
+
+    commands.insert_resource(HealthBarAssets {
+        quad: meshes.add(Rectangle::new(1.0, 1.0)),
+        background,
+        player_fill,
+        enemy_fill,
+        lag,
+        shield,
+        digits,
+    });
+}
+
+fn cleanup_health_bars(mut commands: Commands, mut pool: ResMut<HealthBarPool>, query: Query<Entity, With<HealthBar>>) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+    pool.0.clear();
+}
+
+/// Gives every new combatant a bar, reusing a pooled one when possible.
+#[allow(clippy::type_complexity)]
+fn attach_health_bars(
+    mut commands: Commands,
+    assets: Res<HealthBarAssets>,
+    mut pool: ResMut<HealthBarPool>,
+    target_query: Query<(Entity, Has<Player>, Option<&Level>), Added<Health>>,
+    mut bar_query: Query<(&mut HealthBar, &Children)>,
+    mut layer_query: Query<(&HealthBarLayer, &mut MeshMaterial3d<StandardMaterial>)>,
+    digit_query: Query<(), With<LevelDigit>>,
+) {
+    for (target, is_player, level) in &target_query {
+        let fill = if is_player { &assets.player_fill } else { &assets.enemy_fill };
+
+        let pooled = pool.0.pop().filter(|bar| bar_query.contains(*bar));
+        let bar = match pooled {
+            Some(bar) => {
+                let (mut health_bar, children) = bar_query.get_mut(bar).unwrap();
+                health_bar.attach(target);
+                for child in children {
+                    if digit_query.contains(*child) {
+                        commands.entity(*child).despawn();
+                    } else if let Ok((HealthBarLayer::Fill, mut material)) = layer_query.get_mut(*child) {
+                        material.0 = fill.clone();
+                    }
+                }
+                bar
+            }
+            None => spawn_bar(&mut commands, &assets, target, fill),
+        };
+
+        // Level digits sit just left of the bar
+        if let Some(level) = level {
+            let text = level.0.to_string();
+            let right = -BAR_SIZE.x / 2.0 - BAR_BORDER - LEVEL_DIGIT_SIZE * 0.35;
+            commands.entity(bar).with_children(|parent| {
+                for (i, digit) in text.bytes().rev().enumerate() {
+                    parent.spawn((
+                        LevelDigit,
+                        Mesh3d(assets.quad.clone()),
+                        MeshMaterial3d(assets.digits[(digit - b'0') as usize].clone()),
+                        Transform::from_xyz(right - i as f32 * LEVEL_DIGIT_SIZE * 0.6, 0.0, 0.0)
+                            .with_scale(Vec3::splat(LEVEL_DIGIT_SIZE)),
+                        Visibility::Inherited,
+                        InheritedVisibility::default(),
+                    ));
+                }
+            });
+        }
+    }
+}
+
+fn spawn_bar(commands: &mut Commands, assets: &HealthBarAssets, target: Entity, fill: &Handle<StandardMaterial>) -> Entity {
+    let mut health_bar = HealthBar { target: None, lag: 1.0, last: 1.0, lag_delay: 0.0 };
+    health_bar.attach(target);
+
+    commands.spawn((
+        health_bar,
+        Transform::default(),
+        Visibility::Hidden,
+        InheritedVisibility::default(),
+    )).with_children(|parent| {
+        parent.spawn((
+            Mesh3d(assets.quad.clone()),
+            MeshMaterial3d(assets.background.clone()),
+            Transform::from_scale((BAR_SIZE + BAR_BORDER * 2.0).extend(1.0)),
+            Visibility::Inherited,
+            InheritedVisibility::default(),
+        ));
+        for (layer, material) in [
+            (HealthBarLayer::Lag, &assets.lag),
+            (HealthBarLayer::Fill, fill),
+            (HealthBarLayer::Shield, &assets.shield),
+        ] {
+            parent.spawn((
+                layer,
+                Mesh3d(assets.quad.clone()),
+                MeshMaterial3d(material.clone()),
+                Transform::from_xyz(0.0, 0.0, layer.depth()),
+                Visibility::Inherited,
+                InheritedVisibility::default(),
+            ));
+        }
+    }).id()
+}
+
+/// Follows targets, faces the camera and sizes the layers. Bars whose target
+/// is gone go back to the pool.
+#[allow(clippy::type_complexity)]
+fn update_health_bars(
+    time: Res<Time>,
+    mut pool: ResMut<HealthBarPool>,
+    camera_query: Query<&Transform, (With<Camera3d>, Without<HealthBar>, Without<HealthBarLayer>)>,
+    target_query: Query<(&Transform, &Health, Has<Player>, &Visibility, Has<Respawning>), (Without<HealthBar>, Without<HealthBarLayer>)>,
+    mut bar_query: Query<(Entity, &mut HealthBar, &mut Transform, &mut Visibility, &Children), Without<HealthBarLayer>>,
+    mut layer_query: Query<(&HealthBarLayer, &mut Transform, &mut Visibility), Without<HealthBar>>,
+) {
+    let Ok(camera_transform) = camera_query.get_single() else {
+        return;
+    };
+
+    for (entity, mut bar, mut transform, mut visibility, children) in &mut bar_query {
+        let Some(target) = bar.target else {
+            continue;
+        };
+        let Ok((target_transform, health, is_player, target_visibility, respawning)) = target_query.get(target) else {
+            bar.target = None;
+            *visibility = Visibility::Hidden;
+            pool.0.push(entity);
+            continue;
+        };
+
+        // The damage chunk waits a moment after each hit, then catches up
+        let fraction = (health.current / health.max).clamp(0.0, 1.0);
+        if fraction < bar.last {
+            bar.lag_delay = LAG_DELAY_SECS;
+        }
+        bar.last = fraction;
+        if fraction >= bar.lag {
+            bar.lag = fraction;
+        } else if bar.lag_delay > 0.0 {
+            bar.lag_delay -= time.delta_secs();
+        } else {
+            bar.lag = (bar.lag - LAG_DRAIN_RATE * time.delta_secs()).max(fraction);
+        }
+
+        // Enemies only show a bar once hurt, and never from inside the fog
+        let shown = if is_player {
+            !respawning
+        } else {
+            *target_visibility != Visibility::Hidden && (fraction < 1.0 || health.shield > 0.0 || bar.lag < 1.0)
+        };
+        visibility.set_if_neq(if shown { Visibility::Visible } else { Visibility::Hidden });
+        if !shown {
+            continue;
+        }
+
+        transform.translation = target_transform.translation + Vec3::Y * BAR_HEIGHT;
+        transform.rotation = camera_transform.rotation;
+
+        // Shield extends past current health; the bar rescales if they overflow max
+        let total = health.max.max(health.current.max(0.0) + health.shield);
+        let left = -BAR_SIZE.x / 2.0;
+        let fill_width = BAR_SIZE.x * health.current.max(0.0) / total;
+        for child in children {
+            let Ok((layer, mut layer_transform, mut layer_visibility)) = layer_query.get_mut(*child) else {
+                continue;
+            };
+            let (start, width) = match layer {
+                HealthBarLayer::Lag => (0.0, BAR_SIZE.x * bar.lag * health.max / total),
+                HealthBarLayer::Fill => (0.0, fill_width),
+                HealthBarLayer::Shield => (fill_width, BAR_SIZE.x * health.shield / total),
+            };
+            layer_visibility.set_if_neq(if width > 0.001 { Visibility::Inherited } else { Visibility::Hidden });
+            layer_transform.translation.x = left + start + width / 2.0;
+            layer_transform.scale = Vec3::new(width.max(0.001), BAR_SIZE.y, 1.0);
+        }
+    }
+}
@@ -0,0 +1,527 @@
+use std::collections::BTreeMap;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{GameState, PlayState};
+use crate::settings::close_settings;
+
+/// Bindings shown, and settable, per action on the bindings screen.
+const BINDING_SLOTS: usize = 2;
+
+/// Something the player can do, bound to any number of keys and buttons.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    /// Move to the cursor, or lock onto the enemy under it.
+    Move,
+    Fire,
+    AttackMove,
+    Stop,
+    HoldPosition,
+    /// Held to add orders to the queue instead of replacing it.
+    Queue,
+    /// Blink to the cursor, aimed while held.
+    Ability1,
+    /// Roll along the current heading.
+    Ability2,
+    ToggleRange,
+    ToggleDebug,
+    CameraLock,
+    CameraCenter,
+    CameraDrag,
+    /// Pauses, and backs out of menus.
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 18] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Move,
+        Action::Fire,
+        Action::AttackMove,
+        Action::Stop,
+        Action::HoldPosition,
+        Action::Queue,
+        Action::Ability1,
+        Action::Ability2,
+        Action::ToggleRange,
+        Action::ToggleDebug,
+        Action::CameraLock,
+        Action::CameraCenter,
+        Action::CameraDrag,
+        Action::Pause,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Move => "Move / target",
+            Action::Fire => "Fire",
+            Action::AttackMove => "Attack-move",
+            Action::Stop => "Stop",
+            Action::HoldPosition => "Hold position",
+            Action::Queue => "Queue orders",
+            Action::Ability1 => "Blink",
+            Action::Ability2 => "Roll",
+            Action::ToggleRange => "Range ring",
+            Action::ToggleDebug => "Debug overlay",
+            Action::CameraLock => "Lock camera",
+            Action::CameraCenter => "Center camera",
+            Action::CameraDrag => "Drag camera",
+            Action::Pause => "Pause / back",
+        }
+    }
+
+    fn default_bindings(self) -> Vec<Binding> {
+        use Binding::{Key, Mouse};
+
+        match self {
+            Action::MoveUp => vec![Key(KeyCode::KeyW), Key(KeyCode::ArrowUp)],
+            Action::MoveDown => vec![Key(KeyCode::KeyS), Key(KeyCode::ArrowDown)],
+            Action::MoveLeft => vec![Key(KeyCode::KeyA), Key(KeyCode::ArrowLeft)],
+            Action::MoveRight => vec![Key(KeyCode::KeyD), Key(KeyCode::ArrowRight)],
+            Action::Move => vec![Mouse(MouseButton::Right)],
+            Action::Fire => vec![Mouse(MouseButton::Left), Key(KeyCode::Space)],
+            Action::AttackMove => vec![Key(KeyCode::KeyX)],
+            Action::Stop => vec![Key(KeyCode::KeyZ)],
+            Action::HoldPosition => vec![Key(KeyCode::KeyH)],
+            Action::Queue => vec![Key(KeyCode::ShiftLeft), Key(KeyCode::ShiftRight)],
+            Action::Ability1 => vec![Key(KeyCode::KeyF)],
+            Action::Ability2 => vec![Key(KeyCode::KeyE)],
+            Action::ToggleRange => vec![Key(KeyCode::KeyC)],
+            Action::ToggleDebug => vec![Key(KeyCode::F3)],
+            Action::CameraLock => vec![Key(KeyCode::KeyY)],
+            Action::CameraCenter => vec![Key(KeyCode::Tab)],
+            Action::CameraDrag => vec![Mouse(MouseButton::Middle)],
+            Action::Pause => vec![Key(KeyCode::Escape)],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+impl Binding {
+    pub fn label(self) -> String {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{key:?}");
+                name.strip_prefix("Key").or_else(|| name.strip_prefix("Digit")).unwrap_or(&name).to_string()
+            }
+            Binding::Mouse(MouseButton::Left) => "Left click".to_string(),
+            Binding::Mouse(MouseButton::Right) => "Right click".to_string(),
+            Binding::Mouse(MouseButton::Middle) => "Middle click".to_string(),
+            Binding::Mouse(button) => format!("Mouse {button:?}"),
+        }
+    }
+}
+
+/// Which keys and buttons trigger each action. Saved with the settings.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionMap {
+    bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL.into_iter().map(|action| (action, action.default_bindings())).collect(),
+        }
+    }
+}
+
+impl ActionMap {
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Gives actions missing from a map saved by an older version their
+    /// default bindings.
+    pub fn fill_defaults(&mut self) {
+        for action in Action::ALL {
+            self.bindings.entry(action).or_insert_with(|| action.default_bindings());
+        }
+    }
+
+    /// Puts `binding` in `slot`, or clears the slot with `None`. A binding the
+    /// action already has in any slot is left where it is.
+    pub fn set(&mut self, action: Action, slot: usize, binding: Option<Binding>) {
+        let bindings = self.bindings.entry(action).or_default();
+        match binding {
+            Some(binding) if bindings.contains(&binding) => {}
+            Some(binding) if slot < bindings.len() => bindings[slot] = binding,
+            Some(binding) => bindings.push(binding),
+            None if slot < bindings.len() => {
+                bindings.remove(slot);
+            }
+            None => {}
+        }
+    }
+
+    /// Other actions sharing one of `action`'s bindings.
+    pub fn conflicts(&self, action: Action) -> Vec<Action> {
+        let bindings = self.bindings(action);
+        Action::ALL
+            .into_iter()
+            .filter(|other| *other != action)
+            .filter(|other| self.bindings(*other).iter().any(|binding| bindings.contains(binding)))
+            .collect()
+    }
+
+    fn any(&self, action: Action, key: impl Fn(KeyCode) -> bool, button: impl Fn(MouseButton) -> bool) -> bool {
+        self.bindings(action).iter().any(|binding| match *binding {
+            Binding::Key(code) => key(code),
+            Binding::Mouse(code) => button(code),
+        })
+    }
+
+    /// For systems that also need to `consume` the press.
+    pub fn just_pressed(&self, action: Action, keys: &ButtonInput<KeyCode>, mouse: &ButtonInput<MouseButton>) -> bool {
+        self.any(action, |key| keys.just_pressed(key), |button| mouse.just_pressed(button))
+    }
+
+    /// Stops systems later in the frame from seeing `action` pressed, when a
+    /// menu has already handled it.
+    pub fn consume(&self, action: Action, keys: &mut ButtonInput<KeyCode>, mouse: &mut ButtonInput<MouseButton>) {
+        for binding in self.bindings(action) {
+            match *binding {
+                Binding::Key(key) => {
+                    keys.clear_just_pressed(key);
+                }
+                Binding::Mouse(button) => {
+                    mouse.clear_just_pressed(button);
+                }
+            }
+        }
+    }
+}
+
+/// Reads actions through the current `ActionMap`.
+#[derive(SystemParam)]
+pub struct Actions<'w> {
+    map: Res<'w, ActionMap>,
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+}
+
+impl Actions<'_> {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.map.any(action, |key| self.keys.pressed(key), |button| self.mouse.pressed(button))
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.map.just_pressed(action, &self.keys, &self.mouse)
+    }
+
+    /// Released this frame, with no other binding for it still held.
+    pub fn just_released(&self, action: Action) -> bool {
+        self.map.any(action, |key| self.keys.just_released(key), |button| self.mouse.just_released(button))
+            && !self.pressed(action)
+    }
+}
+
+/// Whether the bindings screen is showing, over the settings screen.
+#[derive(Resource, Default)]
+pub struct BindingsMenu {
+    pub open: bool,
+    /// The slot waiting for a key or button press.
+    capturing: Option<(Action, usize)>,
+    /// A press was just captured; it mustn't also click a button.
+    captured: bool,
+}
+
+#[derive(Component)]
+pub struct BindingsUI;
+
+#[derive(Component, Clone, Copy)]
+pub struct BindingSlot {
+    action: Action,
+    slot: usize,
+}
+
+#[derive(Component)]
+pub struct BindingSlotLabel(BindingSlot);
+
+#[derive(Component)]
+pub struct ActionLabel(Action);
+
+#[derive(Component)]
+pub enum BindingsButton {
+    Reset,
+    Back,
+}
+
+pub struct InputMapPlugin;
+
+impl Plugin for InputMapPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ActionMap>()
+            .init_resource::<BindingsMenu>()
+            .add_systems(Update, (
+                capture_binding,
+                close_bindings,
+                bindings_interaction,
+                update_binding_labels,
+                sync_bindings_menu,
+            ).chain().before(close_settings))
+            .add_systems(OnExit(GameState::Menu), hide_bindings)
+            .add_systems(OnExit(PlayState::Paused), hide_bindings);
+    }
+}
+
+/// Binds the next key or button pressed to the slot being captured. Escape
+/// cancels and Backspace clears the slot.
+fn capture_binding(
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut mouse: ResMut<ButtonInput<MouseButton>>,
+    mut menu: ResMut<BindingsMenu>,
+    mut map: ResMut<ActionMap>,
+) {
+    menu.captured = false;
+    let Some((action, slot)) = menu.capturing else {
+        return;
+    };
+
+    let pressed = keys.get_just_pressed().next().copied().map(Binding::Key)
+        .or_else(|| mouse.get_just_pressed().next().copied().map(Binding::Mouse));
+    let Some(binding) = pressed else {
+        return;
+    };
+
+    match binding {
+        Binding::Key(KeyCode::Escape) => {}
+        Binding::Key(KeyCode::Backspace) => map.set(action, slot, None),
+        binding => map.set(action, slot, Some(binding)),
+    }
+    menu.capturing = None;
+    menu.captured = true;
+    // Nothing else should act on the press
+    keys.reset_all();
+    mouse.reset_all();
+}
+
+/// The pause action backs out of the bindings screen, and is consumed so the
+/// settings screen behind it stays open.
+pub fn close_bindings(
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut mouse: ResMut<ButtonInput<MouseButton>>,
+    map: Res<ActionMap>,
+    mut menu: ResMut<BindingsMenu>,
+) {
+    if menu.open && map.just_pressed(Action::Pause, &keys, &mouse) {
+        map.consume(Action::Pause, &mut keys, &mut mouse);
+        menu.open = false;
+    }
+}
+
+fn hide_bindings(mut menu: ResMut<BindingsMenu>) {
+    menu.open = false;
+    menu.capturing = None;
+}
+
+fn bindings_interaction(
+    mut menu: ResMut<BindingsMenu>,
+    mut map: ResMut<ActionMap>,
+    slot_query: Query<(&Interaction, &BindingSlot), Changed<Interaction>>,
+    button_query: Query<(&Interaction, &BindingsButton), Changed<Interaction>>,
+) {
+    if menu.captured {
+        return;
+    }
+    for (interaction, slot) in &slot_query {
+        if *interaction == Interaction::Pressed {
+            menu.capturing = Some((slot.action, slot.slot));
+        }
+    }
+    for (interaction, button) in &button_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            BindingsButton::Reset => {
+                *map = ActionMap::default();
+                menu.capturing = None;
+            }
+            BindingsButton::Back => {
+                menu.open = false;
+                menu.capturing = None;
+            }
+        }
+    }
+}
+
+fn update_binding_labels(
+    menu: Res<BindingsMenu>,
+    map: Res<ActionMap>,
+    mut slot_query: Query<(&BindingSlotLabel, &mut Text), Without<ActionLabel>>,
+    mut action_query: Query<(&ActionLabel, &mut Text, &mut TextColor)>,
+) {
+    for (label, mut text) in &mut slot_query {
+        let slot = label.0;
+        let wanted = if menu.capturing == Some((slot.action, slot.slot)) {
+            "Press a key...".to_string()
+        } else {
+            map.bindings(slot.action).get(slot.slot).map_or("-".to_string(), |binding| binding.label())
+        };
+        if text.0 != wanted {
+            text.0 = wanted;
+        }
+    }
+
+    // Conflicting actions turn red and say what they clash with
+    for (label, mut text, mut color) in &mut action_query {
+        let conflicts = map.conflicts(label.0);
+        let wanted = match conflicts.first() {
+            Some(other) => format!("{} (also {})", label.0.name(), other.name()),
+            None => label.0.name().to_string(),
+        };
+        if text.0 != wanted {
+            text.0 = wanted;
+        }
+        let wanted_color = if conflicts.is_empty() { Color::WHITE } else { Color::srgb(1.0, 0.3, 0.3) };
+        if color.0 != wanted_color {
+            color.0 = wanted_color;
+        }
+    }
+}
+
+/// Shows or hides the bindings screen to match `BindingsMenu`.
+fn sync_bindings_menu(
+    mut commands: Commands,
+    mut last_open: Local<bool>,
+    menu: Res<BindingsMenu>,
+    ui_query: Query<Entity, With<BindingsUI>>,
+) {
+    if menu.open == *last_open {
+        return;
+    }
+    *last_open = menu.open;
+    for entity in &ui_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    if menu.open {
+        setup_bindings_menu(&mut commands);
+    }
+}
+
+fn setup_bindings_menu(commands: &mut Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.95)),
+        // Over the settings screen it opens from
+        ZIndex(30),
+        FocusPolicy::Block,
+        BindingsUI,
+    )).with_children(|parent| {
+        parent.spawn((
+            Text::new("KEY BINDINGS"),
+            TextFont::from_font_size(50.0),
+            TextColor(Color::WHITE),
+        ));
+        parent.spawn((
+            Text::new("Click a slot, then press a key or button. Esc cancels, Backspace clears."),
+            TextFont::from_font_size(18.0),
+            TextColor(Color::srgb(0.8, 0.8, 0.0)),
+            Node {
+                margin: UiRect::bottom(Val::Px(10.0)),
+                ..default()
+            },
+        ));
+
+        // Two columns of actions, so the list fits without scrolling
+        parent.spawn(Node {
+            flex_direction: FlexDirection::Row,
+            ..default()
+        }).with_children(|row| {
+            for column_actions in Action::ALL.chunks(Action::ALL.len().div_ceil(2)) {
+                row.spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    margin: UiRect::horizontal(Val::Px(15.0)),
+                    ..default()
+                }).with_children(|column| {
+                    for action in column_actions {
+                        column.spawn(Node {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            margin: UiRect::top(Val::Px(4.0)),
+                            ..default()
+                        }).with_children(|line| {
+                            line.spawn((
+                                Text::new(action.name()),
+                                TextFont::from_font_size(18.0),
+                                TextColor(Color::WHITE),
+                                Node {
+                                    width: Val::Px(260.0),
+                                    ..default()
+                                },
+                                ActionLabel(*action),
+                            ));
+                            for slot in 0..BINDING_SLOTS {
+                                let binding_slot = BindingSlot { action: *action, slot };
+                                line.spawn((
+                                    Button,
+                                    Node {
+                                        width: Val::Px(130.0),
+                                        height: Val::Px(32.0),
+                                        margin: UiRect::left(Val::Px(6.0)),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+                                    binding_slot,
+                                )).with_child((Text::default(), TextFont::from_font_size(16.0), BindingSlotLabel(binding_slot)));
+                            }
+                        });
+                    }
+                });
+            }
+        });
+
+        parent.spawn(Node {
+            flex_direction: FlexDirection::Row,
+            ..default()
+        }).with_children(|row| {
+            for (button, label) in [(BindingsButton::Reset, "RESET"), (BindingsButton::Back, "BACK")] {
+                row.spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(200.0),
+                        height: Val::Px(55.0),
+                        margin: UiRect::all(Val::Px(15.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+                    button,
+                )).with_child((Text::new(label), TextFont::from_font_size(28.0)));
+            }
+        });
+    });
+}
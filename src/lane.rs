@@ -0,0 +1,983 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+
+use crate::biome::{Biome, BiomeAssets, PropSet, BIOME_GROUND_WIDTH};
+use crate::daynight::StreetLamp;
+use crate::hazards::{Hazard, HazardKind};
+use crate::props::{Destructible, Interactive};
+use crate::app::{
+    AttackMove, CommandQueue, GameState, Ground, HoverPosition, PlayState, PlayerCommand, Progress, Prop, RunSeed,
+    TargetPosition, LANE_HALF_WIDTH,
+};
+use crate::camera::CameraRig;
+use crate::numbers::FloatingNumber;
+
+/// Road tiles are 1x1 in the pack; one tile spans the whole lane width.
+pub const ROAD_TILE_SIZE: f32 = 2.0 * LANE_HALF_WIDTH;
+pub const SEGMENT_LENGTH: f32 = ROAD_TILE_SIZE;
+/// Segments kept loaded ahead of the furthest progress point.
+pub const LANE_SEGMENTS_AHEAD: i32 = 8;
+/// Segments kept loaded behind the wall before they are despawned.
+pub const LANE_SEGMENTS_BEHIND: i32 = 2;
+/// Once the player is this far from the origin, the whole world is shifted back.
+pub const REBASE_DISTANCE: f32 = 256.0;
+/// Rebase shifts are multiples of this, which is both a whole number of
+/// segments and of ground texture repeats, so the shift is invisible.
+pub const REBASE_STEP: f32 = 80.0;
+
+const BUILDING_SCALE: f32 = 2.5;
+const BUILDING_SETBACK: f32 = LANE_HALF_WIDTH + 3.5;
+const PROP_SCALE: f32 = 6.0;
+const CONSTRUCTION_SCALE: f32 = 8.0;
+/// Bridges are squashed so the deck sits at ground level.
+const BRIDGE_HEIGHT_SCALE: f32 = 4.0;
+/// Chance a street light is a shrine instead.
+const SHRINE_CHANCE: f64 = 0.15;
+/// Chance a street light has an electrified puddle under it.
+const PUDDLE_CHANCE: f64 = 0.3;
+
+pub const BUILDING_MODELS: [&str; 20] = [
+    "Models/GLB_format/building-a.glb",
+    "Models/GLB_format/building-b.glb",
+    "Models/GLB_format/building-c.glb",
+    "Models/GLB_format/building-d.glb",
+    "Models/GLB_format/building-e.glb",
+    "Models/GLB_format/building-f.glb",
+    "Models/GLB_format/building-g.glb",
+    "Models/GLB_format/building-h.glb",
+    "Models/GLB_format/building-i.glb",
+    "Models/GLB_format/building-j.glb",
+    "Models/GLB_format/building-k.glb",
+    "Models/GLB_format/building-l.glb",
+    "Models/GLB_format/building-m.glb",
+    "Models/GLB_format/building-n.glb",
+    "Models/GLB_format/building-o.glb",
+    "Models/GLB_format/building-p.glb",
+    "Models/GLB_format/building-q.glb",
+    "Models/GLB_format/building-r.glb",
+    "Models/GLB_format/building-s.glb",
+    "Models/GLB_format/building-t.glb",
+];
+
+const ROAD_STRAIGHT: &str = "Models/GLB_format/road-straight.glb";
+const ROAD_CROSSING: &str = "Models/GLB_format/road-crossing.glb";
+const ROAD_DRIVEWAY_SINGLE: &str = "Models/GLB_format/road-driveway-single.glb";
+const ROAD_DRIVEWAY_DOUBLE: &str = "Models/GLB_format/road-driveway-double.glb";
+const ROAD_INTERSECTION: &str = "Models/GLB_format/road-intersection.glb";
+const ROAD_CROSSROAD: &str = "Models/GLB_format/road-crossroad.glb";
+const ROAD_BRIDGE: &str = "Models/GLB_format/road-bridge.glb";
+const ROAD_BARRIER: &str = "Models/GLB_format/road-straight-barrier.glb";
+const ROAD_END_ROUND: &str = "Models/GLB_format/road-end-round.glb";
+const ROAD_BEND: &str = "Models/GLB_format/road-bend-sidewalk.glb";
+const ROAD_CURVE: &str = "Models/GLB_format/road-curve.glb";
+const BRIDGE_PILLAR: &str = "Models/GLB_format/bridge-pillar-wide.glb";
+const LIGHT_MODELS: [&str; 2] = [
+    "Models/GLB_format/light-square.glb",
+    "Models/GLB_format/light-curved.glb",
+];
+const SIGN_MODELS: [&str; 3] = [
+    "Models/GLB_format/sign-highway.glb",
+    "Models/GLB_format/sign-highway-wide.glb",
+    "Models/GLB_format/sign-highway-detailed.glb",
+];
+const CONSTRUCTION_BARRIER: &str = "Models/GLB_format/construction-barrier.glb";
+const CONSTRUCTION_CONE: &str = "Models/GLB_format/construction-cone.glb";
+const CONSTRUCTION_LIGHT: &str = "Models/GLB_format/construction-light.glb";
+const DETAIL_TANK: &str = "Models/GLB_format/detail-tank.glb";
+const INDUSTRIAL_MODELS: [&str; 4] = [
+    "Models/GLB_format/chimney-large.glb",
+    "Models/GLB_format/chimney-medium.glb",
+    "Models/GLB_format/chimney-small.glb",
+    DETAIL_TANK,
+];
+/// Highway signs span the lane; only their two legs can be hit.
+const SIGN_LEGS: [Vec3; 2] = [Vec3::new(0.0, 0.0, -0.47), Vec3::new(0.0, 0.0, 0.47)];
+
+/// Every model the lane generator can place, for preloading.
+pub const LANE_MODELS: [&str; 24] = [
+    ROAD_STRAIGHT,
+    ROAD_CROSSING,
+    ROAD_DRIVEWAY_SINGLE,
+    ROAD_DRIVEWAY_DOUBLE,
+    ROAD_INTERSECTION,
+    ROAD_CROSSROAD,
+    ROAD_BRIDGE,
+    ROAD_BARRIER,
+    ROAD_END_ROUND,
+    ROAD_BEND,
+    ROAD_CURVE,
+    BRIDGE_PILLAR,
+    LIGHT_MODELS[0],
+    LIGHT_MODELS[1],
+    SIGN_MODELS[0],
+    SIGN_MODELS[1],
+    SIGN_MODELS[2],
+    CONSTRUCTION_BARRIER,
+    CONSTRUCTION_CONE,
+    CONSTRUCTION_LIGHT,
+    INDUSTRIAL_MODELS[0],
+    INDUSTRIAL_MODELS[1],
+    INDUSTRIAL_MODELS[2],
+    INDUSTRIAL_MODELS[3],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SegmentKind {
+    Straight,
+    Crossing,
+    Driveway,
+    /// Side street branching off one side of the lane (`side` is +1 or -1 on Z).
+    Intersection { side: i8 },
+    Crossroad,
+    Bridge,
+    Construction,
+}
+
+/// What a lane piece is, so systems can tell road from scenery.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PieceKind {
+    Road,
+    /// Biome-textured ground strip under the segment.
+    Ground,
+    Water,
+    Building,
+    Light,
+    Sign,
+    Construction,
+    Industrial,
+    /// Street light that grants a buff when walked past.
+    Shrine,
+    /// Chimney that periodically vents smoke over the lane.
+    Chimney,
+    /// Hazard spawner; the piece only carries its position.
+    Hazard(HazardKind),
+    Tree,
+}
+
+#[derive(Clone, Debug)]
+pub struct LanePiece {
+    pub kind: PieceKind,
+    /// Model path, or `None` for pieces built from primitives (trees, water, ground).
+    pub model: Option<&'static str>,
+    pub transform: Transform,
+}
+
+/// Axis-aligned footprint on the ground plane (X/Z) that blocks movement.
+#[derive(Clone, Copy, Debug)]
+pub struct Obstacle {
+    pub min: Vec2,
+    pub max: Vec2,
+    /// Tall enough to block line of sight, not just movement.
+    pub blocks_sight: bool,
+}
+
+impl Obstacle {
+    pub fn new(center: Vec3, half_extents: Vec2) -> Self {
+        let center = center.xz();
+        Self {
+            min: center - half_extents,
+            max: center + half_extents,
+            blocks_sight: false,
+        }
+    }
+
+    pub fn blocking_sight(mut self) -> Self {
+        self.blocks_sight = true;
+        self
+    }
+
+    /// Position a circle of `radius` at `pos` must move to in order to stop overlapping.
+    pub fn push_out(&self, pos: Vec2, radius: f32) -> Option<Vec2> {
+        let closest = pos.clamp(self.min, self.max);
+        let delta = pos - closest;
+        let dist = delta.length();
+        if dist >= radius {
+            return None;
+        }
+        if dist > 1e-4 {
+            return Some(closest + delta / dist * radius);
+        }
+
+        // Center is inside the box: leave through the nearest face
+        [
+            (pos.x - self.min.x, Vec2::new(self.min.x - radius, pos.y)),
+            (self.max.x - pos.x, Vec2::new(self.max.x + radius, pos.y)),
+            (pos.y - self.min.y, Vec2::new(pos.x, self.min.y - radius)),
+            (self.max.y - pos.y, Vec2::new(pos.x, self.max.y + radius)),
+        ]
+        .into_iter()
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, out)| out)
+    }
+
+    /// Whether the segment from `a` to `b` crosses the footprint.
+    pub fn intersects_segment(&self, a: Vec2, b: Vec2) -> bool {
+        let d = b - a;
+        let mut t_min = 0.0f32;
+        let mut t_max = 1.0f32;
+        for (start, delta, lo, hi) in [(a.x, d.x, self.min.x, self.max.x), (a.y, d.y, self.min.y, self.max.y)] {
+            if delta.abs() < 1e-6 {
+                if start < lo || start > hi {
+                    return false;
+                }
+                continue;
+            }
+            let (t0, t1) = {
+                let t0 = (lo - start) / delta;
+                let t1 = (hi - start) / delta;
+                if t0 < t1 { (t0, t1) } else { (t1, t0) }
+            };
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_min > t_max {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// One road tile's worth of lane. The segment with index `i` is centered on
+/// `x = -i * SEGMENT_LENGTH`, so higher indices are further forward.
+#[derive(Clone, Debug)]
+pub struct LaneSegment {
+    pub biome: Biome,
+    pub x_min: f32,
+    pub x_max: f32,
+    pub pieces: Vec<LanePiece>,
+    pub obstacles: Vec<Obstacle>,
+}
+
+impl LaneSegment {
+    fn translate_x(&mut self, dx: f32) {
+        self.x_min += dx;
+        self.x_max += dx;
+        for piece in &mut self.pieces {
+            piece.transform.translation.x += dx;
+        }
+        for obstacle in &mut self.obstacles {
+            obstacle.min.x += dx;
+            obstacle.max.x += dx;
+        }
+    }
+}
+
+/// Deterministic lane generator. Each segment is derived from the run seed
+/// and its index alone, so segments can be produced in any order.
+///
+/// The lane itself always runs straight along -X, which the wall, camera and
+/// movement clamps rely on; bend and curve tiles only cap side streets.
+#[derive(Clone, Copy, Debug)]
+pub struct LaneGenerator {
+    pub seed: u64,
+}
+
+impl LaneGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    fn rng_for(&self, index: i32) -> StdRng {
+        StdRng::seed_from_u64(self.seed ^ (index as i64 as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+
+    fn kind_for(&self, index: i32, biome: Biome, rng: &mut StdRng) -> SegmentKind {
+        // Keep the spawn area plain
+        if index.abs() <= 1 {
+            return SegmentKind::Straight;
+        }
+        let weights = biome.def().segment_weights;
+        let mut roll = rng.gen_range(0..weights.iter().sum::<u32>());
+        let choice = weights.iter().position(|weight| {
+            if roll < *weight {
+                return true;
+            }
+            roll -= weight;
+            false
+        });
+        match choice.unwrap_or(0) {
+            0 => SegmentKind::Straight,
+            1 => SegmentKind::Crossing,
+            2 => SegmentKind::Driveway,
+            3 => SegmentKind::Intersection { side: if rng.gen_bool(0.5) { 1 } else { -1 } },
+            4 => SegmentKind::Crossroad,
+            5 => SegmentKind::Bridge,
+            _ => SegmentKind::Construction,
+        }
+    }
+
+    pub fn segment(&self, index: i32) -> LaneSegment {
+        let mut rng = self.rng_for(index);
+        let biome = Biome::for_segment(self.seed, index);
+        let kind = self.kind_for(index, biome, &mut rng);
+        let center_x = -(index as f32) * SEGMENT_LENGTH;
+        let center = Vec3::new(center_x, 0.0, 0.0);
+        let mut segment = LaneSegment {
+            biome,
+            x_min: center_x - SEGMENT_LENGTH / 2.0,
+            x_max: center_x + SEGMENT_LENGTH / 2.0,
+            pieces: Vec::new(),
+            obstacles: Vec::new(),
+        };
+
+        let road_scale = Vec3::new(ROAD_TILE_SIZE, 1.0, ROAD_TILE_SIZE);
+        let road = |model: &'static str, transform: Transform| LanePiece {
+            kind: PieceKind::Road,
+            model: Some(model),
+            transform,
+        };
+
+        segment.pieces.push(LanePiece {
+            kind: PieceKind::Ground,
+            model: None,
+            transform: Transform::from_translation(center + Vec3::Y * 0.002)
+                .with_scale(Vec3::new(SEGMENT_LENGTH, 1.0, BIOME_GROUND_WIDTH)),
+        });
+
+        // Lane tile
+        match kind {
+            SegmentKind::Straight => {
+                segment.pieces.push(road(ROAD_STRAIGHT, Transform::from_translation(center).with_scale(road_scale)));
+            }
+            SegmentKind::Crossing => {
+                segment.pieces.push(road(ROAD_CROSSING, Transform::from_translation(center).with_scale(road_scale)));
+                push_traffic(&mut segment, center);
+            }
+            SegmentKind::Driveway => {
+                let model = if rng.gen_bool(0.5) { ROAD_DRIVEWAY_SINGLE } else { ROAD_DRIVEWAY_DOUBLE };
+                let flip = if rng.gen_bool(0.5) { std::f32::consts::PI } else { 0.0 };
+                segment.pieces.push(road(model, Transform::from_translation(center)
+                    .with_rotation(Quat::from_rotation_y(flip))
+                    .with_scale(road_scale)));
+            }
+            SegmentKind::Intersection { side } => {
+                // The tile branches toward -Z; flip it for the +Z side
+                let rotation = if side > 0 { std::f32::consts::PI } else { 0.0 };
+                segment.pieces.push(road(ROAD_INTERSECTION, Transform::from_translation(center)
+                    .with_rotation(Quat::from_rotation_y(rotation))
+                    .with_scale(road_scale)));
+                push_side_street(&mut segment, center, side as f32, &mut rng);
+                push_traffic(&mut segment, center);
+            }
+            SegmentKind::Crossroad => {
+                segment.pieces.push(road(ROAD_CROSSROAD, Transform::from_translation(center).with_scale(road_scale)));
+                push_side_street(&mut segment, center, 1.0, &mut rng);
+                push_side_street(&mut segment, center, -1.0, &mut rng);
+                push_traffic(&mut segment, center);
+            }
+            SegmentKind::Bridge => {
+                segment.pieces.push(road(ROAD_BRIDGE, Transform::from_translation(center - Vec3::Y * 0.5 * BRIDGE_HEIGHT_SCALE)
+                    .with_scale(Vec3::new(ROAD_TILE_SIZE, BRIDGE_HEIGHT_SCALE, ROAD_TILE_SIZE))));
+                for side in [1.0, -1.0] {
+                    segment.pieces.push(LanePiece {
+                        kind: PieceKind::Water,
+                        model: None,
+                        transform: Transform::from_translation(center + Vec3::new(0.0, 0.02, side * (LANE_HALF_WIDTH + 15.0)))
+                            .with_scale(Vec3::new(SEGMENT_LENGTH, 1.0, 30.0)),
+                    });
+                    segment.pieces.push(LanePiece {
+                        kind: PieceKind::Construction,
+                        model: Some(BRIDGE_PILLAR),
+                        transform: Transform::from_translation(center + Vec3::new(0.0, -1.0, side * (LANE_HALF_WIDTH + 0.5)))
+                            .with_scale(Vec3::splat(PROP_SCALE * 2.0)),
+                    });
+                }
+            }
+            SegmentKind::Construction => {
+                segment.pieces.push(road(ROAD_BARRIER, Transform::from_translation(center).with_scale(road_scale)));
+                push_construction_site(&mut segment, center, &mut rng);
+            }
+        }
+
+        // Roadside dressing
+        let open_sides: Vec<f32> = match kind {
+            SegmentKind::Intersection { side } => vec![-(side as f32)],
+            SegmentKind::Crossroad | SegmentKind::Bridge => vec![],
+            _ => vec![1.0, -1.0],
+        };
+        for side in open_sides {
+            push_roadside(&mut segment, center, side, biome, &mut rng);
+        }
+
+        // Street lights at every other segment boundary, signs over junctions
+        if index % 2 == 0 && kind != SegmentKind::Bridge {
+            // Parks get the curved lamps, everything else the square ones
+            let light = if biome.def().prop_set == PropSet::Trees { LIGHT_MODELS[1] } else { LIGHT_MODELS[0] };
+            for side in [1.0, -1.0] {
+                let pos = Vec3::new(segment.x_max, 0.0, side * (LANE_HALF_WIDTH + 0.3));
+                // The lamp arm points toward -Z
+                let rotation = if side > 0.0 { 0.0 } else { std::f32::consts::PI };
+                let kind = if rng.gen_bool(SHRINE_CHANCE) { PieceKind::Shrine } else { PieceKind::Light };
+                segment.pieces.push(LanePiece {
+                    kind,
+                    model: Some(light),
+                    transform: Transform::from_translation(pos)
+                        .with_rotation(Quat::from_rotation_y(rotation))
+                        .with_scale(Vec3::splat(PROP_SCALE)),
+                });
+                segment.obstacles.push(Obstacle::new(pos, Vec2::splat(0.3)));
+                if kind == PieceKind::Light && rng.gen_bool(PUDDLE_CHANCE) {
+                    segment.pieces.push(LanePiece {
+                        kind: PieceKind::Hazard(HazardKind::Puddle),
+                        model: None,
+                        transform: Transform::from_xyz(segment.x_max, 0.0, side * (LANE_HALF_WIDTH - 1.5)),
+                    });
+                }
+            }
+        }
+        if matches!(kind, SegmentKind::Intersection { .. } | SegmentKind::Crossroad) && rng.gen_bool(0.6) {
+            segment.pieces.push(LanePiece {
+                kind: PieceKind::Sign,
+                model: Some(SIGN_MODELS[rng.gen_range(0..SIGN_MODELS.len())]),
+                transform: Transform::from_translation(Vec3::new(segment.x_min + 1.0, 0.0, 0.0))
+                    .with_scale(Vec3::new(ROAD_TILE_SIZE, 10.0, ROAD_TILE_SIZE)),
+            });
+        }
+
+        segment
+    }
+}
+
+/// Side street running off the lane toward `side`, capped with a dead end or a bend.
+fn push_side_street(segment: &mut LaneSegment, center: Vec3, side: f32, rng: &mut StdRng) {
+    let length = rng.gen_range(1..=3);
+    let street_rotation = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+    for step in 1..=length {
+        segment.pieces.push(LanePiece {
+            kind: PieceKind::Road,
+            model: Some(ROAD_STRAIGHT),
+            transform: Transform::from_translation(center + Vec3::Z * side * ROAD_TILE_SIZE * step as f32)
+                .with_rotation(street_rotation)
+                .with_scale(Vec3::new(ROAD_TILE_SIZE, 1.0, ROAD_TILE_SIZE)),
+        });
+    }
+
+    let end = center + Vec3::Z * side * ROAD_TILE_SIZE * (length + 1) as f32;
+    let (model, rotation) = match rng.gen_range(0..3) {
+        // Dead end: the rounded cap opens toward +X, turn it to face the lane
+        0 => (ROAD_END_ROUND, Quat::from_rotation_y(side * std::f32::consts::FRAC_PI_2)),
+        1 => (ROAD_BEND, Quat::from_rotation_y(if side > 0.0 { 0.0 } else { std::f32::consts::PI })),
+        _ => (ROAD_CURVE, Quat::from_rotation_y(if side > 0.0 { std::f32::consts::FRAC_PI_2 } else { -std::f32::consts::FRAC_PI_2 })),
+    };
+    // Curves are 2x2 tiles, so shift them half a tile further out
+    let end = if model == ROAD_CURVE { end + Vec3::Z * side * ROAD_TILE_SIZE * 0.5 } else { end };
+    segment.pieces.push(LanePiece {
+        kind: PieceKind::Road,
+        model: Some(model),
+        transform: Transform::from_translation(end)
+            .with_rotation(rotation)
+            .with_scale(Vec3::new(ROAD_TILE_SIZE, 1.0, ROAD_TILE_SIZE)),
+    });
+
+    // Buildings flank the side street
+    for offset in [-1.0, 1.0] {
+        let pos = center + Vec3::new(offset * (ROAD_TILE_SIZE / 2.0 + 3.0), 0.0, side * (ROAD_TILE_SIZE + 2.0));
+        push_building(segment, pos, if offset > 0.0 { -std::f32::consts::FRAC_PI_2 } else { std::f32::consts::FRAC_PI_2 }, rng);
+    }
+}
+
+/// Cars periodically cross the lane here.
+fn push_traffic(segment: &mut LaneSegment, center: Vec3) {
+    segment.pieces.push(LanePiece {
+        kind: PieceKind::Hazard(HazardKind::Traffic),
+        model: None,
+        transform: Transform::from_translation(center),
+    });
+}
+
+/// Barriers, cones and lights blocking part of the lane.
+fn push_construction_site(segment: &mut LaneSegment, center: Vec3, rng: &mut StdRng) {
+    let side = if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+    let x = center.x + rng.gen_range(-4.0..4.0);
+    let inner = rng.gen_range(0.5..2.5);
+
+    // A row of barriers across one half of the lane, each its own obstacle
+    // so breaking one opens a gap
+    let mut z = inner;
+    while z < LANE_HALF_WIDTH - 1.0 {
+        let pos = Vec3::new(x, 0.0, side * z);
+        segment.pieces.push(LanePiece {
+            kind: PieceKind::Construction,
+            model: Some(CONSTRUCTION_BARRIER),
+            transform: Transform::from_translation(pos).with_scale(Vec3::splat(CONSTRUCTION_SCALE)),
+        });
+        segment.obstacles.push(Obstacle::new(pos, Vec2::new(0.5, 0.9)));
+        z += 1.8;
+    }
+
+    // Cones leading up to the barrier and a warning light at its tip
+    for step in 1..=3 {
+        let pos = Vec3::new(x + step as f32 * 2.5, 0.0, side * (inner + step as f32 * 1.2).min(LANE_HALF_WIDTH - 0.5));
+        segment.pieces.push(LanePiece {
+            kind: PieceKind::Construction,
+            model: Some(CONSTRUCTION_CONE),
+            transform: Transform::from_translation(pos).with_scale(Vec3::splat(CONSTRUCTION_SCALE)),
+        });
+        segment.obstacles.push(Obstacle::new(pos, Vec2::splat(0.3)));
+    }
+    segment.pieces.push(LanePiece {
+        kind: PieceKind::Construction,
+        model: Some(CONSTRUCTION_LIGHT),
+        transform: Transform::from_translation(Vec3::new(x, 0.0, side * (inner - 0.3).max(0.2)))
+            .with_scale(Vec3::splat(CONSTRUCTION_SCALE)),
+    });
+
+    // Debris rains down around the site
+    segment.pieces.push(LanePiece {
+        kind: PieceKind::Hazard(HazardKind::DebrisZone),
+        model: None,
+        transform: Transform::from_xyz(x, 0.0, side * (inner + LANE_HALF_WIDTH) / 2.0),
+    });
+}
+
+/// Buildings or the biome's own props along one edge of the lane.
+fn push_roadside(segment: &mut LaneSegment, center: Vec3, side: f32, biome: Biome, rng: &mut StdRng) {
+    let def = biome.def();
+    // Buildings face the lane
+    let facing = if side > 0.0 { std::f32::consts::PI } else { 0.0 };
+    for offset in [-SEGMENT_LENGTH / 4.0, SEGMENT_LENGTH / 4.0] {
+        let pos = center + Vec3::new(offset, 0.0, side * BUILDING_SETBACK);
+        if rng.gen_bool(def.building_chance) {
+            push_building(segment, pos, facing, rng);
+            continue;
+        }
+
+        match def.prop_set {
+            PropSet::Buildings | PropSet::Trees => {
+                for jitter in [-2.0, 2.0] {
+                    segment.pieces.push(LanePiece {
+                        kind: PieceKind::Tree,
+                        model: None,
+                        transform: Transform::from_translation(pos + Vec3::new(jitter, 0.0, -side * 2.0 + rng.gen_range(-1.0..1.0))),
+                    });
+                }
+            }
+            PropSet::Construction => {
+                for (model, z) in [(CONSTRUCTION_BARRIER, -2.5), (CONSTRUCTION_CONE, -1.0), (CONSTRUCTION_LIGHT, 0.5)] {
+                    segment.pieces.push(LanePiece {
+                        kind: PieceKind::Construction,
+                        model: Some(model),
+                        transform: Transform::from_translation(pos + Vec3::new(rng.gen_range(-2.0..2.0), 0.0, side * z))
+                            .with_rotation(Quat::from_rotation_y(rng.gen_range(0.0..std::f32::consts::TAU)))
+                            .with_scale(Vec3::splat(CONSTRUCTION_SCALE)),
+                    });
+                }
+            }
+            PropSet::Industrial => {
+                let model = INDUSTRIAL_MODELS[rng.gen_range(0..INDUSTRIAL_MODELS.len())];
+                segment.pieces.push(LanePiece {
+                    kind: if model == DETAIL_TANK { PieceKind::Industrial } else { PieceKind::Chimney },
+                    model: Some(model),
+                    transform: Transform::from_translation(pos)
+                        .with_rotation(Quat::from_rotation_y(facing))
+                        .with_scale(Vec3::splat(BUILDING_SCALE * 1.5)),
+                });
+                segment.obstacles.push(Obstacle::new(pos, Vec2::splat(BUILDING_SCALE)).blocking_sight());
+            }
+        }
+    }
+}
+
+fn push_building(segment: &mut LaneSegment, pos: Vec3, rotation: f32, rng: &mut StdRng) {
+    segment.pieces.push(LanePiece {
+        kind: PieceKind::Building,
+        model: Some(BUILDING_MODELS[rng.gen_range(0..BUILDING_MODELS.len())]),
+        transform: Transform::from_translation(pos)
+            .with_rotation(Quat::from_rotation_y(rotation))
+            .with_scale(Vec3::splat(BUILDING_SCALE)),
+    });
+    segment.obstacles.push(Obstacle::new(pos, Vec2::splat(BUILDING_SCALE * 1.1)).blocking_sight());
+}
+
+/// The loaded part of the lane as data: which segments exist and what blocks
+/// movement. Positions are in world space; `origin_x` is where segment 0 sits
+/// after any floating-origin rebases.
+#[derive(Resource)]
+pub struct LaneLayout {
+    pub generator: LaneGenerator,
+    pub segments: BTreeMap<i32, LaneSegment>,
+    pub origin_x: f32,
+}
+
+impl LaneLayout {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            generator: LaneGenerator::new(seed),
+            segments: BTreeMap::new(),
+            origin_x: 0.0,
+        }
+    }
+
+    pub fn index_at(&self, x: f32) -> i32 {
+        (-(x - self.origin_x) / SEGMENT_LENGTH).round() as i32
+    }
+
+    /// World X of the centre of segment `index`, loaded or not.
+    pub fn segment_x(&self, index: i32) -> f32 {
+        self.origin_x - index as f32 * SEGMENT_LENGTH
+    }
+
+    /// Generates the segment if it is not loaded yet. Returns it only when newly created.
+    pub fn ensure_segment(&mut self, index: i32) -> Option<&LaneSegment> {
+        if self.segments.contains_key(&index) {
+            return None;
+        }
+        let mut segment = self.generator.segment(index);
+        segment.translate_x(self.origin_x);
+        Some(self.segments.entry(index).or_insert(segment))
+    }
+
+    fn rebase(&mut self, dx: f32) {
+        self.origin_x += dx;
+        for segment in self.segments.values_mut() {
+            segment.translate_x(dx);
+        }
+    }
+
+    /// Obstacles in the segments overlapping `[x_min, x_max]`.
+    pub fn obstacles_near(&self, x_min: f32, x_max: f32) -> impl Iterator<Item = &Obstacle> {
+        let first = self.index_at(x_max) - 1;
+        let last = self.index_at(x_min) + 1;
+        self.segments.range(first..=last).flat_map(|(_, segment)| segment.obstacles.iter())
+    }
+
+    /// Drops the obstacles under `pos`, e.g. once the prop there is destroyed.
+    pub fn remove_obstacles_at(&mut self, pos: Vec3) {
+        // Lamps and shrines sit on segment boundaries, so check the neighbours too
+        let index = self.index_at(pos.x);
+        let flat = pos.xz();
+        for (_, segment) in self.segments.range_mut(index - 1..=index + 1) {
+            segment.obstacles.retain(|obstacle| flat.cmplt(obstacle.min).any() || flat.cmpgt(obstacle.max).any());
+        }
+    }
+
+    /// Pushes a circle at `pos` out of every obstacle it overlaps.
+    pub fn resolve_collision(&self, pos: Vec3, radius: f32) -> Vec3 {
+        let mut flat = pos.xz();
+        for obstacle in self.obstacles_near(pos.x - radius, pos.x + radius) {
+            if let Some(out) = obstacle.push_out(flat, radius) {
+                flat = out;
+            }
+        }
+        Vec3::new(flat.x, pos.y, flat.y)
+    }
+
+    /// Whether a straight walk from `a` to `b` hits an obstacle.
+    pub fn path_blocked(&self, a: Vec3, b: Vec3) -> bool {
+        self.obstacles_near(a.x.min(b.x), a.x.max(b.x))
+            .any(|obstacle| obstacle.intersects_segment(a.xz(), b.xz()))
+    }
+
+    /// Whether a building stands between `a` and `b`.
+    pub fn sight_blocked(&self, a: Vec3, b: Vec3) -> bool {
+        self.obstacles_near(a.x.min(b.x), a.x.max(b.x))
+            .any(|obstacle| obstacle.blocks_sight && obstacle.intersects_segment(a.xz(), b.xz()))
+    }
+
+    /// Steers `dir` around obstacles within `lookahead`, preferring the smallest turn.
+    pub fn steer(&self, pos: Vec3, dir: Vec3, lookahead: f32) -> Vec3 {
+        for angle in [0.0f32, 0.5, -0.5, 1.0, -1.0, 1.5, -1.5] {
+            let candidate = Quat::from_rotation_y(angle) * dir;
+            if !self.path_blocked(pos, pos + candidate * lookahead) {
+                return candidate;
+            }
+        }
+        dir
+    }
+}
+
+/// Road surface and water pieces (as opposed to `Prop` scenery).
+#[derive(Component)]
+pub struct LaneTile;
+
+/// Lane entities remember their segment so the chunk can be unloaded as a whole.
+#[derive(Component)]
+pub struct LaneSegmentId(pub i32);
+
+#[derive(Resource)]
+pub struct LaneAssets {
+    pub trunk_mesh: Handle<Mesh>,
+    pub leaves_mesh: Handle<Mesh>,
+    pub trunk_material: Handle<StandardMaterial>,
+    pub leaves_material: Handle<StandardMaterial>,
+    pub water_mesh: Handle<Mesh>,
+    pub water_material: Handle<StandardMaterial>,
+}
+
+pub struct LanePlugin;
+
+impl Plugin for LanePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(GameState::Playing), setup_lane)
+            .add_systems(Update, stream_lane_chunks.run_if(in_state(PlayState::Running)))
+            .add_systems(
+                PostUpdate,
+                rebase_origin
+                    .before(bevy::transform::TransformSystem::TransformPropagate)
+                    .run_if(in_state(PlayState::Running)),
+            )
+            .add_systems(OnExit(GameState::Playing), cleanup_lane);
+    }
+}
+
+fn setup_lane(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    biome_assets: Res<BiomeAssets>,
+    run_seed: Res<RunSeed>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let lane_assets = LaneAssets {
+        trunk_mesh: meshes.add(Cylinder::new(0.3, 1.0)),
+        leaves_mesh: meshes.add(Cone::new(1.2, 2.5)),
+        trunk_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.4, 0.2, 0.1),
+            ..default()
+        }),
+        leaves_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.1, 0.5, 0.1),
+            ..default()
+        }),
+        water_mesh: meshes.add(Plane3d::default().mesh().size(1.0, 1.0)),
+        water_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.15, 0.35, 0.6),
+            perceptual_roughness: 0.1,
+            reflectance: 0.6,
+            ..default()
+        }),
+    };
+
+    let mut layout = LaneLayout::new(run_seed.0);
+    for index in -LANE_SEGMENTS_BEHIND..=LANE_SEGMENTS_AHEAD {
+        if let Some(segment) = layout.ensure_segment(index) {
+            spawn_segment(&mut commands, &asset_server, &lane_assets, &biome_assets, index, segment);
+        }
+    }
+
+    commands.insert_resource(layout);
+    commands.insert_resource(lane_assets);
+}
+
+pub fn spawn_segment(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    lane_assets: &LaneAssets,
+    biome_assets: &BiomeAssets,
+    index: i32,
+    segment: &LaneSegment,
+) {
+    for piece in &segment.pieces {
+        match (piece.kind, piece.model) {
+            (PieceKind::Ground, _) => {
+                commands.spawn((
+                    LaneTile,
+                    LaneSegmentId(index),
+                    Mesh3d(biome_assets.ground_mesh.clone()),
+                    MeshMaterial3d(biome_assets.ground_material(segment.biome)),
+                    piece.transform,
+                    Visibility::Visible,
+                    InheritedVisibility::default(),
+                ));
+            }
+            (PieceKind::Tree, _) => spawn_tree(commands, lane_assets, index, piece.transform.translation),
+            (PieceKind::Hazard(kind), _) => {
+                commands.spawn((Hazard::new(kind), LaneSegmentId(index), piece.transform));
+            }
+            (PieceKind::Water, _) => {
+                commands.spawn((
+                    LaneTile,
+                    LaneSegmentId(index),
+                    Mesh3d(lane_assets.water_mesh.clone()),
+                    MeshMaterial3d(lane_assets.water_material.clone()),
+                    piece.transform,
+                    Visibility::Visible,
+                    InheritedVisibility::default(),
+                ));
+            }
+            (PieceKind::Road, Some(model)) => {
+                commands.spawn((
+                    LaneTile,
+                    LaneSegmentId(index),
+                    SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset(model))),
+                    piece.transform,
+                    Visibility::Visible,
+                    InheritedVisibility::default(),
+                ));
+            }
+            (PieceKind::Shrine, Some(model)) => {
+                commands.spawn((
+                    Prop,
+                    LaneSegmentId(index),
+                    Interactive::shrine(),
+                    SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset(model))),
+                    piece.transform,
+                    Visibility::Visible,
+                    InheritedVisibility::default(),
+                )).with_child((
+                    // Model space, so this is the lamp head
+                    PointLight {
+                        color: Color::srgb(1.0, 0.85, 0.4),
+                        intensity: 200_000.0,
+                        range: 12.0,
+                        ..default()
+                    },
+                    Transform::from_xyz(0.0, 0.55, -0.15),
+                ));
+            }
+            (PieceKind::Chimney, Some(model)) => {
+                commands.spawn((
+                    Prop,
+                    LaneSegmentId(index),
+                    Interactive::chimney(),
+                    SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset(model))),
+                    piece.transform,
+                    Visibility::Visible,
+                    InheritedVisibility::default(),
+                ));
+            }
+            (_, Some(model)) => {
+                let mut prop = commands.spawn((
+                    Prop,
+                    LaneSegmentId(index),
+                    SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset(model))),
+                    piece.transform,
+                    Visibility::Visible,
+                    InheritedVisibility::default(),
+                ));
+                if let Some(destructible) = destructible_for(piece) {
+                    prop.insert(destructible);
+                }
+                if piece.kind == PieceKind::Light {
+                    prop.with_child(StreetLamp::bundle());
+                }
+            }
+            (_, None) => {}
+        }
+    }
+}
+
+/// Health and hit area for the props that can be shot apart.
+fn destructible_for(piece: &LanePiece) -> Option<Destructible> {
+    match (piece.kind, piece.model?) {
+        (PieceKind::Construction, CONSTRUCTION_BARRIER) => Some(Destructible::new(60.0, 0.9, &[Vec3::ZERO])),
+        (PieceKind::Construction, CONSTRUCTION_CONE) => Some(Destructible::new(20.0, 0.5, &[Vec3::ZERO])),
+        (PieceKind::Construction, CONSTRUCTION_LIGHT) => Some(Destructible::new(30.0, 0.5, &[Vec3::ZERO])),
+        (PieceKind::Light, _) => Some(Destructible::new(50.0, 0.5, &[Vec3::ZERO])),
+        (PieceKind::Sign, _) => Some(Destructible::new(120.0, 0.6, &SIGN_LEGS)),
+        _ => None,
+    }
+}
+
+fn spawn_tree(commands: &mut Commands, lane_assets: &LaneAssets, index: i32, pos: Vec3) {
+    commands.spawn((
+        Prop,
+        LaneSegmentId(index),
+        Mesh3d(lane_assets.trunk_mesh.clone()),
+        MeshMaterial3d(lane_assets.trunk_material.clone()),
+        Transform::from_translation(pos + Vec3::Y * 0.5),
+        Visibility::Visible,
+        InheritedVisibility::default(),
+    ));
+    commands.spawn((
+        Prop,
+        LaneSegmentId(index),
+        Mesh3d(lane_assets.leaves_mesh.clone()),
+        MeshMaterial3d(lane_assets.leaves_material.clone()),
+        Transform::from_translation(pos + Vec3::Y * 2.0),
+        Visibility::Visible,
+        InheritedVisibility::default(),
+    ));
+}
+
+/// Loads segments ahead of the furthest progress and unloads the ones behind the wall.
+fn stream_lane_chunks(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    lane_assets: Res<LaneAssets>,
+    biome_assets: Res<BiomeAssets>,
+    progress: Res<Progress>,
+    mut layout: ResMut<LaneLayout>,
+    chunk_query: Query<(Entity, &LaneSegmentId)>,
+) {
+    let front = layout.index_at(progress.min_x) + LANE_SEGMENTS_AHEAD;
+    let back = layout.index_at(progress.wall_x) - LANE_SEGMENTS_BEHIND;
+
+    for index in back..=front {
+        if let Some(segment) = layout.ensure_segment(index) {
+            spawn_segment(&mut commands, &asset_server, &lane_assets, &biome_assets, index, segment);
+        }
+    }
+
+    let unloaded: Vec<i32> = layout.segments.range(..back).map(|(index, _)| *index).collect();
+    if unloaded.is_empty() {
+        return;
+    }
+    for index in &unloaded {
+        layout.segments.remove(index);
+    }
+    for (entity, segment_id) in &chunk_query {
+        if segment_id.0 < back {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Floating origin: once the player strays far from the origin, shift every
+/// world entity and every stored world position back toward it.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn rebase_origin(
+    mut layout: ResMut<LaneLayout>,
+    mut progress: ResMut<Progress>,
+    mut hover_pos: ResMut<HoverPosition>,
+    mut rig: ResMut<CameraRig>,
+    mut transform_query: Query<&mut Transform, (Without<Parent>, Without<Node>, Without<Ground>)>,
+    mut target_query: Query<&mut TargetPosition>,
+    mut queue_query: Query<&mut CommandQueue>,
+    mut attack_move_query: Query<&mut AttackMove>,
+    mut number_query: Query<&mut FloatingNumber>,
+) {
+    if progress.min_x > -REBASE_DISTANCE {
+        return;
+    }
+
+    let offset = Vec3::X * (-progress.min_x / REBASE_STEP).round() * REBASE_STEP;
+
+    for mut transform in &mut transform_query {
+        transform.translation += offset;
+    }
+    for mut target in &mut target_query {
+        target.0 += offset;
+    }
+    for mut queue in &mut queue_query {
+        for command in queue.0.iter_mut() {
+            match command {
+                PlayerCommand::Move(pos) | PlayerCommand::AttackMove(pos) | PlayerCommand::Dash(pos) => *pos += offset,
+                PlayerCommand::Attack(_) => {}
+            }
+        }
+    }
+    for mut attack_move in &mut attack_move_query {
+        attack_move.0 += offset;
+    }
+    for mut number in &mut number_query {
+        number.rebase(offset);
+    }
+
+    progress.min_x += offset.x;
+    progress.wall_x += offset.x;
+    hover_pos.world += offset;
+    rig.rebase(offset);
+    layout.rebase(offset.x);
+}
+
+fn cleanup_lane(mut commands: Commands, query: Query<Entity, With<LaneTile>>) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<LaneLayout>();
+    commands.remove_resource::<LaneAssets>();
+}
@@ -1,4 +1,23 @@
+mod animation;
 mod app;
+mod audio;
+mod biome;
+mod camera;
+mod daynight;
+mod feel;
+mod graphics;
+mod hazards;
+mod healthbar;
+mod input;
+mod lane;
+mod minimap;
+mod numbers;
+mod particles;
+mod pause;
+mod props;
+mod settings;
+mod vision;
+mod weather;
 
 use bevy::prelude::*;
 use bevy::asset::AssetMetaCheck;
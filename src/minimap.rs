@@ -0,0 +1,303 @@
+use bevy::color::ColorToPacked;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::ui::RelativeCursorPosition;
+
+use crate::app::{Boss, Enemy, EnemyArchetype, GameState, PlayState, Player, PlayingUI, Progress, RunSeed, LANE_HALF_WIDTH};
+use crate::biome::{Biome, BIOME_SECTION_SEGMENTS};
+use crate::camera::CameraRig;
+use crate::lane::LaneLayout;
+use crate::props::Pickup;
+use crate::vision::VisionGrid;
+
+/// World units shown ahead of and behind the player, and to either side of the lane centre.
+const MAP_AHEAD: f32 = 72.0;
+const MAP_BEHIND: f32 = 24.0;
+const MAP_HALF_WIDTH: f32 = 24.0;
+const TEXELS_PER_UNIT: f32 = 2.0;
+/// On-screen size of the map in pixels.
+const MAP_SCREEN_SIZE: Vec2 = Vec2::new(150.0, 300.0);
+/// Half size of the box drawn around the camera's focus.
+const VIEW_BOX_HALF: Vec2 = Vec2::new(14.0, 14.0);
+
+/// Corner map of the lane, redrawn every frame. Left click or drag pans the
+/// camera there, detaching it from the player.
+#[derive(Component)]
+pub struct Minimap {
+    image: Handle<Image>,
+}
+
+/// Minimap interaction state, for systems that must ignore clicks on it.
+pub type MinimapInteraction<'w, 's> = Query<'w, 's, &'static Interaction, With<Minimap>>;
+
+/// Whether the cursor is over the minimap or dragging on it.
+pub fn over_minimap(query: &MinimapInteraction) -> bool {
+    query.iter().any(|interaction| *interaction != Interaction::None)
+}
+
+/// Mapping between world positions and minimap texels. Forward (-X) is up
+/// and the map scrolls with the player.
+struct MapFrame {
+    top_x: f32,
+}
+
+impl MapFrame {
+    fn size() -> UVec2 {
+        (Vec2::new(2.0 * MAP_HALF_WIDTH, MAP_AHEAD + MAP_BEHIND) * TEXELS_PER_UNIT).as_uvec2()
+    }
+
+    fn new(player: Vec3) -> Self {
+        Self { top_x: player.x - MAP_AHEAD }
+    }
+
+    /// Ground position under a point given in 0..1 map coordinates.
+    fn to_world(&self, uv: Vec2) -> Vec3 {
+        let size = Self::size().as_vec2() / TEXELS_PER_UNIT;
+        Vec3::new(self.top_x + uv.y * size.y, 0.0, MAP_HALF_WIDTH - uv.x * size.x)
+    }
+
+    fn to_texel(&self, pos: Vec3) -> Vec2 {
+        Vec2::new(MAP_HALF_WIDTH - pos.z, pos.x - self.top_x) * TEXELS_PER_UNIT
+    }
+}
+
+/// Texel buffer of the map image with a few drawing helpers.
+struct Canvas<'a> {
+    data: &'a mut [u8],
+    size: UVec2,
+}
+
+impl Canvas<'_> {
+    fn put(&mut self, texel: IVec2, color: [u8; 4]) {
+        if texel.x < 0 || texel.y < 0 || texel.x >= self.size.x as i32 || texel.y >= self.size.y as i32 {
+            return;
+        }
+        let index = (texel.y as usize * self.size.x as usize + texel.x as usize) * 4;
+        self.data[index..index + 4].copy_from_slice(&color);
+    }
+
+    fn fill_rect(&mut self, min: Vec2, max: Vec2, color: [u8; 4]) {
+        let (min, max) = (min.min(max).floor().as_ivec2(), min.max(max).ceil().as_ivec2());
+        for y in min.y..max.y {
+            for x in min.x..max.x {
+                self.put(IVec2::new(x, y), color);
+            }
+        }
+    }
+
+    fn outline_rect(&mut self, min: Vec2, max: Vec2, color: [u8; 4]) {
+        let (min, max) = (min.min(max).round().as_ivec2(), min.max(max).round().as_ivec2());
+        for x in min.x..=max.x {
+            self.put(IVec2::new(x, min.y), color);
+            self.put(IVec2::new(x, max.y), color);
+        }
+        for y in min.y..=max.y {
+            self.put(IVec2::new(min.x, y), color);
+            self.put(IVec2::new(max.x, y), color);
+        }
+    }
+
+    fn dot(&mut self, center: Vec2, radius: f32, color: [u8; 4]) {
+        let reach = radius.ceil() as i32;
+        let base = center.floor().as_ivec2();
+        for y in -reach..=reach {
+            for x in -reach..=reach {
+                let texel = base + IVec2::new(x, y);
+                if (texel.as_vec2() + 0.5).distance(center) <= radius {
+                    self.put(texel, color);
+                }
+            }
+        }
+    }
+
+    /// Ringed dot for a boss, pinned inside the map edges so off-map bosses
+    /// still show which way they are.
+    fn boss_marker(&mut self, center: Vec2) {
+        let center = center.clamp(Vec2::splat(4.0), self.size.as_vec2() - 4.0);
+        self.dot(center, 4.0, [40, 0, 0, 255]);
+        self.dot(center, 3.0, [255, 60, 40, 255]);
+        self.dot(center, 1.5, [255, 220, 80, 255]);
+    }
+
+    /// Arrow pointing along `dir`, in texel space.
+    fn arrow(&mut self, center: Vec2, dir: Vec2, size: f32, color: [u8; 4]) {
+        let side = dir.perp();
+        let corners = [center + dir * size, center - dir * size * 0.6 + side * size * 0.7, center - dir * size * 0.6 - side * size * 0.7];
+        let reach = size.ceil() as i32;
+        let base = center.floor().as_ivec2();
+        for y in -reach..=reach {
+            for x in -reach..=reach {
+                let texel = base + IVec2::new(x, y);
+                let p = texel.as_vec2() + 0.5;
+                let sides = [0, 1, 2].map(|i| {
+                    let (a, b) = (corners[i], corners[(i + 1) % 3]);
+                    (b - a).perp_dot(p - a)
+                });
+                if sides.iter().all(|side| *side >= 0.0) || sides.iter().all(|side| *side <= 0.0) {
+                    self.put(texel, color);
+                }
+            }
+        }
+    }
+}
+
+fn rgba(color: Color) -> [u8; 4] {
+    color.to_srgba().to_u8_array()
+}
+
+fn darken(color: [u8; 4], factor: f32) -> [u8; 4] {
+    let [r, g, b, a] = color;
+    [(r as f32 * factor) as u8, (g as f32 * factor) as u8, (b as f32 * factor) as u8, a]
+}
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(GameState::Playing), setup_minimap)
+            .add_systems(Update, (
+                pan_from_minimap,
+                draw_minimap,
+            ).chain().run_if(in_state(PlayState::Running)));
+    }
+}
+
+fn setup_minimap(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let size = MapFrame::size();
+    let mut image = Image::new_fill(
+        Extent3d { width: size.x, height: size.y, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = ImageSampler::nearest();
+    let image = images.add(image);
+
+    // Cleaned up with the rest of the HUD
+    commands.spawn((
+        Minimap { image: image.clone() },
+        ImageNode::new(image),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(20.0),
+            right: Val::Px(20.0),
+            width: Val::Px(MAP_SCREEN_SIZE.x),
+            height: Val::Px(MAP_SCREEN_SIZE.y),
+            border: UiRect::all(Val::Px(2.0)),
+            ..default()
+        },
+        BorderColor(Color::srgba(1.0, 1.0, 1.0, 0.6)),
+        Interaction::default(),
+        RelativeCursorPosition::default(),
+        PlayingUI,
+    ));
+}
+
+fn pan_from_minimap(
+    mut rig: ResMut<CameraRig>,
+    player_query: Query<&Transform, With<Player>>,
+    minimap_query: Query<(&Interaction, &RelativeCursorPosition), With<Minimap>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    for (interaction, cursor) in &minimap_query {
+        // Pressed stays set while dragging off the map, so clamp to its edges
+        if let (Interaction::Pressed, Some(uv)) = (interaction, cursor.normalized) {
+            rig.pan_to(MapFrame::new(player_transform.translation).to_world(uv.clamp(Vec2::ZERO, Vec2::ONE)));
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_minimap(
+    run_seed: Res<RunSeed>,
+    layout: Res<LaneLayout>,
+    progress: Res<Progress>,
+    vision: Res<VisionGrid>,
+    rig: Res<CameraRig>,
+    mut images: ResMut<Assets<Image>>,
+    minimap_query: Query<&Minimap>,
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<(&Transform, &EnemyArchetype), With<Enemy>>,
+    boss_query: Query<&Transform, (With<Boss>, With<Enemy>)>,
+    pickup_query: Query<&Transform, With<Pickup>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let frame = MapFrame::new(player_transform.translation);
+    let size = MapFrame::size();
+
+    for minimap in &minimap_query {
+        let Some(image) = images.get_mut(&minimap.image) else {
+            continue;
+        };
+        let mut canvas = Canvas { data: &mut image.data, size };
+
+        // Ground and road, tinted by the biome of each row
+        for y in 0..size.y {
+            let row_x = frame.to_world(Vec2::new(0.0, (y as f32 + 0.5) / size.y as f32)).x;
+            let ground = rgba(Biome::for_segment(run_seed.0, layout.index_at(row_x)).def().ground_tint);
+            for x in 0..size.x {
+                let pos = frame.to_world((UVec2::new(x, y).as_vec2() + 0.5) / size.as_vec2());
+                let color = if pos.z.abs() <= LANE_HALF_WIDTH { [70, 70, 75, 255] } else { darken(ground, 0.6) };
+                canvas.put(UVec2::new(x, y).as_ivec2(), color);
+            }
+        }
+
+        let bottom_x = frame.top_x + MAP_AHEAD + MAP_BEHIND;
+        for obstacle in layout.obstacles_near(frame.top_x, bottom_x) {
+            let color = if obstacle.blocks_sight { [35, 35, 45, 255] } else { [120, 110, 90, 255] };
+            let (min, max) = (Vec3::new(obstacle.min.x, 0.0, obstacle.min.y), Vec3::new(obstacle.max.x, 0.0, obstacle.max.y));
+            canvas.fill_rect(frame.to_texel(min), frame.to_texel(max), color);
+        }
+
+        // Fog of war dims what the player can't currently see
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let pos = frame.to_world((UVec2::new(x, y).as_vec2() + 0.5) / size.as_vec2());
+                if !vision.is_visible(pos) {
+                    let index = ((y * size.x + x) * 4) as usize;
+                    let texel: [u8; 4] = canvas.data[index..index + 4].try_into().unwrap();
+                    canvas.data[index..index + 4].copy_from_slice(&darken(texel, 0.45));
+                }
+            }
+        }
+
+        // The wall and everything behind it
+        let wall_row = frame.to_texel(Vec3::X * progress.wall_x).y;
+        canvas.fill_rect(Vec2::new(0.0, wall_row), Vec2::new(size.x as f32, size.y as f32), [90, 20, 60, 255]);
+        canvas.fill_rect(Vec2::new(0.0, wall_row - 1.0), Vec2::new(size.x as f32, wall_row + 1.0), [200, 60, 255, 255]);
+
+        for transform in &pickup_query {
+            if vision.is_visible(transform.translation) {
+                canvas.dot(frame.to_texel(transform.translation), 1.5, [80, 255, 120, 255]);
+            }
+        }
+        for (transform, archetype) in &enemy_query {
+            if vision.is_visible(transform.translation) {
+                canvas.dot(frame.to_texel(transform.translation), 2.0, rgba(archetype.color()));
+            }
+        }
+
+        // Bosses show through fog, as does the start of the next boss section
+        let upcoming = layout.segment_x(progress.next_boss_section * BIOME_SECTION_SEGMENTS);
+        canvas.boss_marker(frame.to_texel(Vec3::X * upcoming));
+        for transform in &boss_query {
+            canvas.boss_marker(frame.to_texel(transform.translation));
+        }
+
+        let focus = rig.current_focus;
+        let view_half = Vec3::new(VIEW_BOX_HALF.y, 0.0, VIEW_BOX_HALF.x);
+        canvas.outline_rect(frame.to_texel(focus - view_half), frame.to_texel(focus + view_half), [255, 255, 255, 255]);
+
+        let forward = player_transform.forward();
+        let dir = Vec2::new(-forward.z, forward.x).normalize_or(Vec2::NEG_Y);
+        canvas.arrow(frame.to_texel(player_transform.translation), dir, 4.0, [80, 220, 255, 255]);
+    }
+}
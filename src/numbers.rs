@@ -0,0 +1,255 @@
+use bevy::prelude::*;
+
+use crate::app::{DamageEvent, DamageKind, GameState, HealEvent, PlayState, Player, PlayingUI, ScoreEvent};
+use crate::graphics::viewport_to_window;
+
+/// Seconds a number lives, rising and fading out over the last part of it.
+const NUMBER_LIFETIME_SECS: f32 = 0.9;
+const NUMBER_FADE_SECS: f32 = 0.35;
+/// World units per second a number rises.
+const NUMBER_RISE_SPEED: f32 = 2.0;
+/// Height above the target's feet where numbers appear.
+const NUMBER_HEIGHT: f32 = 3.0;
+/// Hits on the same target within this window add to one number. Lingering
+/// damage ticks every frame, so it batches over a longer window.
+const HIT_BATCH_SECS: f32 = 0.15;
+const OVER_TIME_BATCH_SECS: f32 = 0.6;
+/// Beyond this many numbers on screen, new ones are dropped.
+const MAX_NUMBERS: usize = 40;
+/// Glyph height in pixels.
+const GLYPH_SIZE: f32 = 22.0;
+
+/// Digit sprites, `0` to `9`, then the plus sign.
+pub fn number_glyph_paths() -> Vec<String> {
+    (0..10)
+        .map(|digit| format!("PNG/Default/hud_number_{digit}.png"))
+        .chain(std::iter::once("PNG/Default/hud_plus.png".to_string()))
+        .collect()
+}
+
+#[derive(Resource)]
+pub struct NumberGlyphs {
+    digits: Vec<Handle<Image>>,
+    plus: Handle<Image>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumberKind {
+    /// Damage the player dealt.
+    Hit,
+    Crit,
+    /// Damage the player took.
+    Hurt,
+    OverTime,
+    Heal,
+    Score,
+}
+
+impl NumberKind {
+    fn color(self) -> Color {
+        match self {
+            NumberKind::Hit => Color::WHITE,
+            NumberKind::Crit => Color::srgb(1.0, 0.6, 0.1),
+            NumberKind::Hurt => Color::srgb(1.0, 0.25, 0.25),
+            NumberKind::OverTime => Color::srgb(0.75, 0.45, 1.0),
+            NumberKind::Heal => Color::srgb(0.3, 1.0, 0.4),
+            NumberKind::Score => Color::srgb(1.0, 0.85, 0.2),
+        }
+    }
+
+    fn scale(self) -> f32 {
+        match self {
+            NumberKind::Crit => 1.5,
+            NumberKind::Score => 1.2,
+            NumberKind::OverTime => 0.8,
+            _ => 1.0,
+        }
+    }
+
+    /// Gains are written with a leading plus.
+    fn signed(self) -> bool {
+        matches!(self, NumberKind::Heal | NumberKind::Score)
+    }
+
+    fn batch_secs(self) -> f32 {
+        match self {
+            NumberKind::OverTime => OVER_TIME_BATCH_SECS,
+            _ => HIT_BATCH_SECS,
+        }
+    }
+}
+
+/// A number floating over the world, drawn as a row of digit sprites.
+#[derive(Component)]
+pub struct FloatingNumber {
+    /// What it is about, so further hits can be batched into it.
+    target: Option<Entity>,
+    kind: NumberKind,
+    value: f32,
+    anchor: Vec3,
+    age: f32,
+    /// Time since the value last changed.
+    since_batch: f32,
+}
+
+impl FloatingNumber {
+    /// Shifts the anchor, for the floating-origin rebase.
+    pub fn rebase(&mut self, offset: Vec3) {
+        self.anchor += offset;
+    }
+}
+
+pub struct FloatingNumbersPlugin;
+
+impl Plugin for FloatingNumbersPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(GameState::Playing), setup_number_glyphs)
+            .add_systems(Update, (
+                spawn_floating_numbers,
+                update_floating_numbers,
+            ).chain().run_if(in_state(PlayState::Running)));
+    }
+}
+
+fn setup_number_glyphs(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let mut handles: Vec<Handle<Image>> = number_glyph_paths().into_iter().map(|path| asset_server.load(path)).collect();
+    let plus = handles.pop().unwrap();
+    commands.insert_resource(NumberGlyphs { digits: handles, plus });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_floating_numbers(
+    mut commands: Commands,
+    glyphs: Res<NumberGlyphs>,
+    mut damage_events: EventReader<DamageEvent>,
+    mut heal_events: EventReader<HealEvent>,
+    mut score_events: EventReader<ScoreEvent>,
+    player_query: Query<(), With<Player>>,
+    target_query: Query<&Transform>,
+    mut number_query: Query<(Entity, &mut FloatingNumber)>,
+) {
+    let mut popups: Vec<(Option<Entity>, NumberKind, f32, Option<Vec3>)> = Vec::new();
+    // Damage carries its position since a killing blow's target is about to be despawned
+    for event in damage_events.read() {
+        let kind = match event.kind {
+            DamageKind::OverTime => NumberKind::OverTime,
+            _ if player_query.contains(event.target) => NumberKind::Hurt,
+            DamageKind::Hit => NumberKind::Hit,
+            DamageKind::Crit => NumberKind::Crit,
+        };
+        popups.push((Some(event.target), kind, event.amount, Some(event.position)));
+    }
+    for event in heal_events.read() {
+        popups.push((Some(event.target), NumberKind::Heal, event.amount, None));
+    }
+    for event in score_events.read() {
+        popups.push((None, NumberKind::Score, event.points as f32, Some(event.position)));
+    }
+
+    let mut count = number_query.iter().count();
+    for (target, kind, amount, position) in popups {
+        if amount <= 0.0 {
+            continue;
+        }
+
+        // Add to a fresh number for the same target and kind if there is one
+        let batch = target.and_then(|target| {
+            number_query.iter_mut().find(|(_, number)| {
+                number.target == Some(target) && number.kind == kind && number.since_batch < kind.batch_secs()
+            })
+        });
+        if let Some((entity, mut number)) = batch {
+            number.value += amount;
+            number.since_batch = 0.0;
+            number.age = number.age.min(NUMBER_LIFETIME_SECS - NUMBER_FADE_SECS);
+            let value = number.value;
+            commands.entity(entity).despawn_descendants();
+            spawn_glyphs(&mut commands, entity, &glyphs, kind, value);
+            continue;
+        }
+
+        let Some(anchor) = position.or_else(|| target.and_then(|target| target_query.get(target).ok()).map(|transform| transform.translation)) else {
+            continue;
+        };
+        if count >= MAX_NUMBERS {
+            continue;
+        }
+        count += 1;
+
+        let entity = commands.spawn((
+            FloatingNumber { target, kind, value: amount, anchor: anchor + Vec3::Y * NUMBER_HEIGHT, age: 0.0, since_batch: 0.0 },
+            Node {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Row,
+                ..default()
+            },
+            // Hidden until placed on screen
+            Visibility::Hidden,
+            PlayingUI,
+        )).id();
+        spawn_glyphs(&mut commands, entity, &glyphs, kind, amount);
+    }
+}
+
+fn spawn_glyphs(commands: &mut Commands, entity: Entity, glyphs: &NumberGlyphs, kind: NumberKind, value: f32) {
+    let size = GLYPH_SIZE * kind.scale();
+    let text = (value.round() as u32).max(1).to_string();
+    commands.entity(entity).with_children(|parent| {
+        let sign = kind.signed().then(|| glyphs.plus.clone());
+        let digits = text.bytes().map(|digit| glyphs.digits[(digit - b'0') as usize].clone());
+        for image in sign.into_iter().chain(digits) {
+            parent.spawn((
+                ImageNode::new(image).with_color(kind.color()),
+                // The sprites are square with padding around the digit, so overlap them a little
+                Node {
+                    width: Val::Px(size),
+                    height: Val::Px(size),
+                    margin: UiRect::horizontal(Val::Px(-size * 0.15)),
+                    ..default()
+                },
+            ));
+        }
+    });
+}
+
+/// Rises, fades and keeps each number over its point in the world.
+fn update_floating_numbers(
+    mut commands: Commands,
+    time: Res<Time>,
+    window_query: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    mut number_query: Query<(Entity, &mut FloatingNumber, &mut Node, &mut Visibility, &ComputedNode, &Children)>,
+    mut glyph_query: Query<&mut ImageNode>,
+) {
+    let (Ok(window), Ok((camera, camera_transform))) = (window_query.get_single(), camera_query.get_single()) else {
+        return;
+    };
+
+    for (entity, mut number, mut node, mut visibility, computed, children) in &mut number_query {
+        number.age += time.delta_secs();
+        number.since_batch += time.delta_secs();
+        if number.age >= NUMBER_LIFETIME_SECS {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let world = number.anchor + Vec3::Y * NUMBER_RISE_SPEED * number.age;
+        let Ok(screen) = camera.world_to_viewport(camera_transform, world) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        let screen = viewport_to_window(camera, window, screen);
+        let size = computed.size() * computed.inverse_scale_factor();
+        node.left = Val::Px(screen.x - size.x / 2.0);
+        node.top = Val::Px(screen.y - size.y / 2.0);
+        *visibility = Visibility::Inherited;
+
+        let alpha = ((NUMBER_LIFETIME_SECS - number.age) / NUMBER_FADE_SECS).min(1.0);
+        for child in children {
+            if let Ok(mut image) = glyph_query.get_mut(*child) {
+                image.color.set_alpha(alpha);
+            }
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{DamageEvent, DamageKind, Dying, GameState, PlayState, Player, Projectile, ShotEvent};
+use crate::biome::Biome;
+use crate::lane::LaneLayout;
+
+/// Emitter definitions, loaded with the other assets.
+pub const PARTICLE_EFFECT_PATHS: [&str; 5] = [
+    "effects/muzzle_flash.effect.ron",
+    "effects/hit_sparks.effect.ron",
+    "effects/death_dust.effect.ron",
+    "effects/projectile_trail.effect.ron",
+    "effects/level_up.effect.ron",
+];
+/// Beyond this many live particles at full density, new ones are dropped.
+const MAX_PARTICLES: usize = 800;
+/// Colour steps a particle fades through; each is a shared material.
+const PALETTE_STEPS: usize = 8;
+/// Height above a character's feet where hits and level-ups burst.
+const BODY_HEIGHT: f32 = 1.2;
+
+/// Data-defined emitter, loaded from a `.effect.ron` file. Fires `burst`
+/// particles at once, and `rate` per second while attached to something.
+#[derive(Asset, TypePath, Deserialize, Clone, Debug)]
+pub struct ParticleEffect {
+    #[serde(default)]
+    pub burst: u32,
+    #[serde(default)]
+    pub rate: f32,
+    /// Min and max seconds a particle lives.
+    pub lifetime: (f32, f32),
+    /// Min and max initial speed.
+    pub speed: (f32, f32),
+    /// Half-angle of the cone around the emit direction; 180 is all round.
+    #[serde(default = "ParticleEffect::all_round")]
+    pub spread_degrees: f32,
+    /// Downward acceleration; negative floats up.
+    #[serde(default)]
+    pub gravity: f32,
+    /// Fraction of velocity lost per second.
+    #[serde(default)]
+    pub drag: f32,
+    /// Size at birth and at death.
+    pub size: (f32, f32),
+    /// sRGBA at birth and at death.
+    pub start_color: [f32; 4],
+    pub end_color: [f32; 4],
+    /// Emissive strength, so bright effects bloom.
+    #[serde(default)]
+    pub glow: f32,
+}
+
+impl ParticleEffect {
+    fn all_round() -> f32 {
+        180.0
+    }
+}
+
+#[derive(Debug)]
+pub enum ParticleEffectLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl fmt::Display for ParticleEffectLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParticleEffectLoaderError::Io(err) => write!(f, "could not read particle effect: {err}"),
+            ParticleEffectLoaderError::Ron(err) => write!(f, "could not parse particle effect: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ParticleEffectLoaderError {}
+
+impl From<std::io::Error> for ParticleEffectLoaderError {
+    fn from(err: std::io::Error) -> Self {
+        ParticleEffectLoaderError::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for ParticleEffectLoaderError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        ParticleEffectLoaderError::Ron(err)
+    }
+}
+
+#[derive(Default)]
+pub struct ParticleEffectLoader;
+
+impl AssetLoader for ParticleEffectLoader {
+    type Asset = ParticleEffect;
+    type Settings = ();
+    type Error = ParticleEffectLoaderError;
+
+    async fn load(&self, reader: &mut dyn Reader, _settings: &(), _load_context: &mut LoadContext<'_>) -> Result<ParticleEffect, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["effect.ron"]
+    }
+}
+
+/// Handles to the game's effects and the shared particle mesh.
+#[derive(Resource)]
+pub struct ParticleAssets {
+    mesh: Handle<Mesh>,
+    muzzle_flash: Handle<ParticleEffect>,
+    hit_sparks: Handle<ParticleEffect>,
+    death_dust: Handle<ParticleEffect>,
+    projectile_trail: Handle<ParticleEffect>,
+    level_up: Handle<ParticleEffect>,
+}
+
+/// Scales how many particles every effect emits, and the live cap.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParticleSettings {
+    /// 0 to 1.
+    pub density: f32,
+}
+
+impl Default for ParticleSettings {
+    fn default() -> Self {
+        Self { density: 1.0 }
+    }
+}
+
+impl ParticleSettings {
+    fn max_particles(&self) -> usize {
+        (MAX_PARTICLES as f32 * self.density) as usize
+    }
+}
+
+/// Faded colour steps for each loaded effect, rebuilt when its file changes.
+#[derive(Resource, Default)]
+pub struct ParticlePalettes(HashMap<AssetId<ParticleEffect>, Vec<Handle<StandardMaterial>>>);
+
+/// Emits an effect continuously from this entity, backwards along its facing.
+#[derive(Component)]
+pub struct ParticleEmitter {
+    effect: Handle<ParticleEffect>,
+    /// Particles owed since the last one, so low rates still emit.
+    pending: f32,
+}
+
+#[derive(Component)]
+pub struct Particle {
+    effect: AssetId<ParticleEffect>,
+    velocity: Vec3,
+    age: f32,
+    lifetime: f32,
+    step: usize,
+}
+
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_asset::<ParticleEffect>()
+            .init_asset_loader::<ParticleEffectLoader>()
+            .init_resource::<ParticlePalettes>()
+            .init_resource::<ParticleSettings>()
+            .add_systems(OnEnter(GameState::Playing), setup_particles)
+            .add_systems(Update, build_particle_palettes)
+            .add_systems(Update, (
+                attach_trails,
+                emit_event_particles,
+                emit_trails,
+                update_particles,
+            ).chain().run_if(in_state(PlayState::Running)))
+            .add_systems(OnExit(GameState::Playing), cleanup_particles);
+    }
+}
+
+fn setup_particles(mut commands: Commands, asset_server: Res<AssetServer>, mut meshes: ResMut<Assets<Mesh>>) {
+    let [muzzle_flash, hit_sparks, death_dust, projectile_trail, level_up] = PARTICLE_EFFECT_PATHS.map(|path| asset_server.load(path));
+    commands.insert_resource(ParticleAssets {
+        mesh: meshes.add(Circle::new(0.5)),
+        muzzle_flash,
+        hit_sparks,
+        death_dust,
+        projectile_trail,
+        level_up,
+    });
+}
+
+fn cleanup_particles(mut commands: Commands, query: Query<Entity, With<Particle>>) {
+    for entity in &query {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn build_particle_palettes(
+    mut effect_events: EventReader<AssetEvent<ParticleEffect>>,
+    effects: Res<Assets<ParticleEffect>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut palettes: ResMut<ParticlePalettes>,
+) {
+    for event in effect_events.read() {
+        let (AssetEvent::Added { id } | AssetEvent::Modified { id }) = *event else {
+            continue;
+        };
+        let Some(effect) = effects.get(id) else {
+            continue;
+        };
+        let start = Color::srgba(effect.start_color[0], effect.start_color[1], effect.start_color[2], effect.start_color[3]);
+        let end = Color::srgba(effect.end_color[0], effect.end_color[1], effect.end_color[2], effect.end_color[3]);
+        let palette = (0..PALETTE_STEPS)
+            .map(|step| {
+                let color = start.mix(&end, step as f32 / (PALETTE_STEPS - 1) as f32);
+                materials.add(StandardMaterial {
+                    base_color: color,
+                    emissive: color.to_linear() * effect.glow,
+                    alpha_mode: AlphaMode::Blend,
+                    unlit: true,
+                    ..default()
+                })
+            })
+            .collect();
+        palettes.0.insert(id, palette);
+    }
+}
+
+/// A random direction within `spread_degrees` of `dir`.
+fn cone_direction(rng: &mut impl Rng, dir: Vec3, spread_degrees: f32) -> Vec3 {
+    let dir = dir.normalize_or(Vec3::Y);
+    let cos_max = spread_degrees.clamp(0.0, 180.0).to_radians().cos();
+    let cos_theta = rng.gen_range(cos_max.min(1.0)..=1.0);
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = rng.gen_range(0.0..std::f32::consts::TAU);
+    let (a, b) = dir.any_orthonormal_pair();
+    dir * cos_theta + (a * phi.cos() + b * phi.sin()) * sin_theta
+}
+
+/// Spawns up to `count` particles of `effect` at `position`, heading along `dir`.
+#[allow(clippy::too_many_arguments)]
+fn emit(
+    commands: &mut Commands,
+    assets: &ParticleAssets,
+    effects: &Assets<ParticleEffect>,
+    palettes: &ParticlePalettes,
+    budget: &mut usize,
+    effect: &Handle<ParticleEffect>,
+    position: Vec3,
+    dir: Vec3,
+    count: u32,
+) {
+    let (Some(definition), Some(palette)) = (effects.get(effect), palettes.0.get(&effect.id())) else {
+        return;
+    };
+    let mut rng = rand::thread_rng();
+    for _ in 0..count {
+        if *budget == 0 {
+            return;
+        }
+        *budget -= 1;
+        let speed = rng.gen_range(definition.speed.0..=definition.speed.1.max(definition.speed.0));
+        commands.spawn((
+            Particle {
+                effect: effect.id(),
+                velocity: cone_direction(&mut rng, dir, definition.spread_degrees) * speed,
+                age: 0.0,
+                lifetime: rng.gen_range(definition.lifetime.0..=definition.lifetime.1.max(definition.lifetime.0)),
+                step: 0,
+            },
+            Mesh3d(assets.mesh.clone()),
+            MeshMaterial3d(palette[0].clone()),
+            Transform::from_translation(position).with_scale(Vec3::splat(definition.size.0)),
+            Visibility::Visible,
+            InheritedVisibility::default(),
+        ));
+    }
+}
+
+fn attach_trails(mut commands: Commands, assets: Res<ParticleAssets>, projectile_query: Query<Entity, Added<Projectile>>) {
+    for entity in &projectile_query {
+        commands.entity(entity).insert(ParticleEmitter { effect: assets.projectile_trail.clone(), pending: 0.0 });
+    }
+}
+
+/// One-off bursts: muzzle flashes, hit sparks, death dust, and a level-up
+/// burst when the run reaches a deeper section and enemies level up.
+#[allow(clippy::too_many_arguments)]
+fn emit_event_particles(
+    mut commands: Commands,
+    assets: Res<ParticleAssets>,
+    effects: Res<Assets<ParticleEffect>>,
+    palettes: Res<ParticlePalettes>,
+    settings: Res<ParticleSettings>,
+    layout: Res<LaneLayout>,
+    mut last_section: Local<Option<i32>>,
+    mut shot_events: EventReader<ShotEvent>,
+    mut damage_events: EventReader<DamageEvent>,
+    dying_query: Query<&Transform, Added<Dying>>,
+    player_query: Query<&Transform, With<Player>>,
+    particle_query: Query<(), With<Particle>>,
+) {
+    let mut budget = settings.max_particles().saturating_sub(particle_query.iter().count());
+    let mut burst = |commands: &mut Commands, effect: &Handle<ParticleEffect>, position: Vec3, dir: Vec3| {
+        let count = effects.get(effect).map_or(0, |definition| (definition.burst as f32 * settings.density).round() as u32);
+        emit(commands, &assets, &effects, &palettes, &mut budget, effect, position, dir, count);
+    };
+
+    for event in shot_events.read() {
+        burst(&mut commands, &assets.muzzle_flash, event.position, event.direction);
+    }
+    for event in damage_events.read() {
+        // Lingering damage ticks every frame
+        if event.kind == DamageKind::OverTime {
+            continue;
+        }
+        burst(&mut commands, &assets.hit_sparks, event.position + Vec3::Y * BODY_HEIGHT, event.direction);
+    }
+    for transform in &dying_query {
+        burst(&mut commands, &assets.death_dust, transform.translation, Vec3::Y);
+    }
+
+    if let Ok(transform) = player_query.get_single() {
+        let section = Biome::section(layout.index_at(transform.translation.x));
+        // A new run starts back at a lower section, which doesn't count
+        if last_section.is_some_and(|last| section > last) {
+            burst(&mut commands, &assets.level_up, transform.translation + Vec3::Y * BODY_HEIGHT, Vec3::Y);
+        }
+        *last_section = Some(section);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn emit_trails(
+    mut commands: Commands,
+    time: Res<Time>,
+    assets: Res<ParticleAssets>,
+    effects: Res<Assets<ParticleEffect>>,
+    palettes: Res<ParticlePalettes>,
+    settings: Res<ParticleSettings>,
+    mut emitter_query: Query<(&mut ParticleEmitter, &GlobalTransform)>,
+    particle_query: Query<(), With<Particle>>,
+) {
+    let mut budget = settings.max_particles().saturating_sub(particle_query.iter().count());
+    for (mut emitter, transform) in &mut emitter_query {
+        let Some(definition) = effects.get(&emitter.effect) else {
+            continue;
+        };
+        emitter.pending += definition.rate * settings.density * time.delta_secs();
+        let count = emitter.pending as u32;
+        emitter.pending -= count as f32;
+        let effect = emitter.effect.clone();
+        emit(&mut commands, &assets, &effects, &palettes, &mut budget, &effect, transform.translation(), transform.back().into(), count);
+    }
+}
+
+/// Moves, shrinks, fades and billboards live particles.
+fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    effects: Res<Assets<ParticleEffect>>,
+    palettes: Res<ParticlePalettes>,
+    camera_query: Query<&Transform, (With<Camera3d>, Without<Particle>)>,
+    mut particle_query: Query<(Entity, &mut Particle, &mut Transform, &mut MeshMaterial3d<StandardMaterial>)>,
+) {
+    let facing = camera_query.get_single().map_or(Quat::IDENTITY, |transform| transform.rotation);
+    let dt = time.delta_secs();
+
+    for (entity, mut particle, mut transform, mut material) in &mut particle_query {
+        particle.age += dt;
+        let (Some(definition), Some(palette)) = (effects.get(particle.effect), palettes.0.get(&particle.effect)) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        if particle.age >= particle.lifetime {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        particle.velocity.y -= definition.gravity * dt;
+        particle.velocity *= (1.0 - definition.drag * dt).max(0.0);
+        transform.translation += particle.velocity * dt;
+        transform.rotation = facing;
+
+        let t = particle.age / particle.lifetime;
+        transform.scale = Vec3::splat(definition.size.0 + (definition.size.1 - definition.size.0) * t);
+        let step = ((t * PALETTE_STEPS as f32) as usize).min(PALETTE_STEPS - 1);
+        if step != particle.step {
+            particle.step = step;
+            material.0 = palette[step].clone();
+        }
+    }
+}
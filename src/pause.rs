@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+use bevy::window::{WindowFocused, WindowOccluded};
+
+use crate::app::{GameState, PlayState};
+use crate::input::{Action, Actions};
+use crate::settings::{close_settings, SettingsMenu};
+
+#[derive(Component)]
+pub struct PauseMenuUI;
+
+#[derive(Component)]
+pub enum PauseButton {
+    Resume,
+    Settings,
+    Restart,
+    QuitToMenu,
+}
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, (toggle_pause.after(close_settings), auto_pause).run_if(in_state(GameState::Playing)))
+            .add_systems(Update, pause_menu_interaction.run_if(in_state(PlayState::Paused)))
+            .add_systems(OnEnter(PlayState::Paused), (pause_time, setup_pause_menu))
+            .add_systems(OnExit(PlayState::Paused), (resume_time, cleanup_pause_menu));
+    }
+}
+
+fn toggle_pause(
+    actions: Actions,
+    state: Res<State<PlayState>>,
+    mut next_state: ResMut<NextState<PlayState>>,
+) {
+    if actions.just_pressed(Action::Pause) {
+        next_state.set(match state.get() {
+            PlayState::Running => PlayState::Paused,
+            PlayState::Paused => PlayState::Running,
+        });
+    }
+}
+
+/// Pauses when the window loses focus or is hidden, e.g. the browser tab is
+/// switched away from.
+fn auto_pause(
+    mut focus_events: EventReader<WindowFocused>,
+    mut occluded_events: EventReader<WindowOccluded>,
+    state: Res<State<PlayState>>,
+    mut next_state: ResMut<NextState<PlayState>>,
+) {
+    let lost_focus = focus_events.read().any(|event| !event.focused);
+    let hidden = occluded_events.read().any(|event| event.occluded);
+    if (lost_focus || hidden) && *state.get() == PlayState::Running {
+        next_state.set(PlayState::Paused);
+    }
+}
+
+/// Stops virtual time outright. A hit-stop's slow-down is a separate speed
+/// setting, so it picks up where it left off on resume.
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn setup_pause_menu(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        // Over the HUD and minimap; removed on leaving the pause, which
+        // also happens when the run ends
+        ZIndex(10),
+        PauseMenuUI,
+    )).with_children(|parent| {
+        parent.spawn((
+            Text::new("PAUSED"),
+            TextFont::from_font_size(80.0),
+            TextColor(Color::WHITE),
+        ));
+
+        for (button, label) in [
+            (PauseButton::Resume, "RESUME"),
+            (PauseButton::Settings, "SETTINGS"),
+            (PauseButton::Restart, "RESTART"),
+            (PauseButton::QuitToMenu, "QUIT TO MENU"),
+        ] {
+            parent.spawn((
+                Button,
+                Node {
+                    width: Val::Px(250.0),
+                    height: Val::Px(60.0),
+                    margin: UiRect::all(Val::Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+                button,
+            )).with_child((Text::new(label), TextFont::from_font_size(30.0)));
+        }
+    });
+}
+
+fn cleanup_pause_menu(mut commands: Commands, query: Query<Entity, With<PauseMenuUI>>) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn pause_menu_interaction(
+    mut settings_menu: ResMut<SettingsMenu>,
+    mut next_play_state: ResMut<NextState<PlayState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    interaction_query: Query<(&Interaction, &PauseButton), (Changed<Interaction>, With<Button>)>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            PauseButton::Resume => next_play_state.set(PlayState::Running),
+            PauseButton::Settings => settings_menu.open = true,
+            PauseButton::Restart => next_state.set(GameState::Restarting),
+            PauseButton::QuitToMenu => next_state.set(GameState::Menu),
+        }
+    }
+}
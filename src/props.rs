@@ -0,0 +1,376 @@
+use bevy::prelude::*;
+use rand::Rng;
+use std::time::Duration;
+
+use crate::app::{DamageEvent, DamageKind, Enemy, GameState, HealEvent, Health, PlayState, Player, Prop, Respawning, PLAYER_BOUNDARY_Z};
+use crate::hazards::{hurt, EnemyTargets, PlayerTargets};
+use crate::lane::{LaneLayout, LaneSegmentId};
+
+/// Chance a destroyed prop leaves a health pickup behind.
+const DROP_CHANCE: f64 = 0.35;
+const PICKUP_HEAL: f32 = 30.0;
+const PICKUP_RADIUS: f32 = 1.5;
+const DEBRIS_PIECES: usize = 6;
+const DEBRIS_LIFETIME_SECS: f32 = 1.0;
+const SHRINE_RADIUS: f32 = 2.5;
+const SHRINE_COOLDOWN_SECS: f32 = 30.0;
+const SHRINE_LIGHT_INTENSITY: f32 = 200_000.0;
+pub const EMPOWERED_SECS: f32 = 10.0;
+pub const EMPOWERED_DAMAGE_MULTIPLIER: f32 = 2.0;
+const CHIMNEY_VENT_SECS: f32 = 8.0;
+const SMOKE_LIFETIME_SECS: f32 = 4.0;
+/// Smoke drifts from the roadside toward the middle of the lane.
+const SMOKE_DRIFT_SPEED: f32 = 2.5;
+const SMOKE_RADIUS: f32 = 3.0;
+const SMOKE_DAMAGE_PER_SEC: f32 = 12.0;
+
+/// Prop that can be shot apart. Hits are tested on the ground plane against
+/// circles of `radius` around each anchor, which is given in model space.
+#[derive(Component)]
+pub struct Destructible {
+    pub health: f32,
+    pub radius: f32,
+    pub anchors: &'static [Vec3],
+}
+
+impl Destructible {
+    pub fn new(health: f32, radius: f32, anchors: &'static [Vec3]) -> Self {
+        Self { health, radius, anchors }
+    }
+
+    pub fn hit_by(&self, transform: &Transform, pos: Vec3) -> bool {
+        self.anchors
+            .iter()
+            .any(|anchor| transform.transform_point(*anchor).xz().distance(pos.xz()) < self.radius)
+    }
+}
+
+/// Prop that does something on its own or when the player comes close.
+#[derive(Component)]
+pub enum Interactive {
+    /// Grants `Empowered` to a player walking past, then recharges.
+    Shrine { cooldown: Timer },
+    /// Vents a damaging smoke cloud every time the timer wraps.
+    Chimney { vent: Timer },
+}
+
+impl Interactive {
+    pub fn shrine() -> Self {
+        // Start charged
+        let mut cooldown = Timer::from_seconds(SHRINE_COOLDOWN_SECS, TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+        Self::Shrine { cooldown }
+    }
+
+    pub fn chimney() -> Self {
+        // Stagger neighbouring chimneys
+        let mut vent = Timer::from_seconds(CHIMNEY_VENT_SECS, TimerMode::Repeating);
+        vent.set_elapsed(Duration::from_secs_f32(rand::thread_rng().gen_range(0.0..CHIMNEY_VENT_SECS)));
+        Self::Chimney { vent }
+    }
+}
+
+/// Shrine buff: player shots deal extra damage until the timer runs out.
+#[derive(Component)]
+pub struct Empowered(pub Timer);
+
+#[derive(Component)]
+pub enum Pickup {
+    Heal(f32),
+}
+
+#[derive(Component)]
+pub struct Debris {
+    velocity: Vec3,
+    timer: Timer,
+}
+
+#[derive(Component)]
+pub struct Smoke {
+    velocity: Vec3,
+    timer: Timer,
+}
+
+#[derive(Resource)]
+pub struct PropAssets {
+    debris_mesh: Handle<Mesh>,
+    debris_material: Handle<StandardMaterial>,
+    pickup_mesh: Handle<Mesh>,
+    pickup_material: Handle<StandardMaterial>,
+    smoke_mesh: Handle<Mesh>,
+    smoke_material: Handle<StandardMaterial>,
+}
+
+pub struct PropsPlugin;
+
+impl Plugin for PropsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(GameState::Playing), setup_prop_assets)
+            .add_systems(Update, (
+                shatter_props,
+                update_debris,
+                collect_pickups,
+                update_shrines,
+                update_empowered,
+                vent_chimneys,
+                update_smoke,
+            ).run_if(in_state(PlayState::Running)))
+            .add_systems(OnExit(GameState::Playing), cleanup_prop_assets);
+    }
+}
+
+fn setup_prop_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(PropAssets {
+        debris_mesh: meshes.add(Cuboid::new(0.25, 0.25, 0.25)),
+        debris_material: materials.add(Color::srgb(0.55, 0.5, 0.45)),
+        pickup_mesh: meshes.add(Sphere::new(0.35)),
+        pickup_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.2, 1.0, 0.3),
+            emissive: LinearRgba::new(0.5, 4.0, 0.8, 1.0),
+            ..default()
+        }),
+        smoke_mesh: meshes.add(Sphere::new(1.0)),
+        smoke_material: materials.add(StandardMaterial {
+            base_color: Color::srgba(0.3, 0.3, 0.28, 0.55),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+    });
+}
+
+fn cleanup_prop_assets(mut commands: Commands) {
+    commands.remove_resource::<PropAssets>();
+}
+
+/// Breaks destroyed props into debris, frees their obstacles and maybe drops a pickup.
+fn shatter_props(
+    mut commands: Commands,
+    prop_assets: Res<PropAssets>,
+    mut layout: ResMut<LaneLayout>,
+    prop_query: Query<(Entity, &Transform, &Destructible)>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (entity, transform, destructible) in &prop_query {
+        if destructible.health > 0.0 {
+            continue;
+        }
+        commands.entity(entity).despawn_recursive();
+
+        for anchor in destructible.anchors {
+            let pos = transform.transform_point(*anchor);
+            layout.remove_obstacles_at(pos);
+
+            for _ in 0..DEBRIS_PIECES {
+                let velocity = Vec3::new(rng.gen_range(-3.0..3.0), rng.gen_range(4.0..8.0), rng.gen_range(-3.0..3.0));
+                commands.spawn((
+                    Prop,
+                    LaneSegmentId(layout.index_at(pos.x)),
+                    Debris { velocity, timer: Timer::from_seconds(DEBRIS_LIFETIME_SECS, TimerMode::Once) },
+                    Mesh3d(prop_assets.debris_mesh.clone()),
+                    MeshMaterial3d(prop_assets.debris_material.clone()),
+                    Transform::from_translation(pos + Vec3::Y * 0.5),
+                    Visibility::Visible,
+                    InheritedVisibility::default(),
+                ));
+            }
+        }
+
+        if rng.gen_bool(DROP_CHANCE) {
+            // Drop it inside the lane so the player can reach it
+            let pos = transform.transform_point(destructible.anchors[0]);
+            let pos = Vec3::new(pos.x, 0.5, pos.z.clamp(1.0 - PLAYER_BOUNDARY_Z, PLAYER_BOUNDARY_Z - 1.0));
+            commands.spawn((
+                Prop,
+                LaneSegmentId(layout.index_at(pos.x)),
+                Pickup::Heal(PICKUP_HEAL),
+                Mesh3d(prop_assets.pickup_mesh.clone()),
+                MeshMaterial3d(prop_assets.pickup_material.clone()),
+                Transform::from_translation(pos),
+                Visibility::Visible,
+                InheritedVisibility::default(),
+            ));
+        }
+    }
+}
+
+fn update_debris(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut debris_query: Query<(Entity, &mut Transform, &mut Debris)>,
+) {
+    for (entity, mut transform, mut debris) in &mut debris_query {
+        debris.timer.tick(time.delta());
+        if debris.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        debris.velocity.y -= 20.0 * time.delta_secs();
+        transform.translation += debris.velocity * time.delta_secs();
+        transform.translation.y = transform.translation.y.max(0.1);
+        transform.scale = Vec3::splat(debris.timer.fraction_remaining());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn collect_pickups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut player_query: Query<(Entity, &Transform, &mut Health), (With<Player>, Without<Respawning>)>,
+    mut pickup_query: Query<(Entity, &mut Transform, &Pickup), Without<Player>>,
+    mut heal_events: EventWriter<HealEvent>,
+) {
+    let Ok((player, player_transform, mut health)) = player_query.get_single_mut() else {
+        return;
+    };
+
+    for (entity, mut transform, pickup) in &mut pickup_query {
+        transform.rotate_y(2.0 * time.delta_secs());
+
+        if transform.translation.xz().distance(player_transform.translation.xz()) > PICKUP_RADIUS {
+            continue;
+        }
+        match pickup {
+            Pickup::Heal(amount) => {
+                let healed = amount.min(health.max - health.current).max(0.0);
+                health.current += healed;
+                heal_events.send(HealEvent { target: player, amount: healed });
+            }
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Shrines glow while charged and empower a player who walks up to them.
+#[allow(clippy::type_complexity)]
+fn update_shrines(
+    mut commands: Commands,
+    time: Res<Time>,
+    player_query: Query<(Entity, &Transform), (With<Player>, Without<Respawning>)>,
+    mut shrine_query: Query<(&Transform, &mut Interactive, &Children)>,
+    mut light_query: Query<&mut PointLight>,
+    mut gizmos: Gizmos,
+) {
+    let player = player_query.get_single().ok();
+
+    for (transform, mut interactive, children) in &mut shrine_query {
+        let Interactive::Shrine { cooldown } = interactive.as_mut() else {
+            continue;
+        };
+        cooldown.tick(time.delta());
+
+        if cooldown.finished() {
+            let center = Vec3::new(transform.translation.x, 0.05, transform.translation.z);
+            gizmos.circle(
+                Isometry3d::new(center, Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+                SHRINE_RADIUS,
+                Color::srgb(1.0, 0.85, 0.4),
+            );
+
+            if let Some((player_entity, player_transform)) = player {
+                if player_transform.translation.xz().distance(transform.translation.xz()) <= SHRINE_RADIUS {
+                    commands
+                        .entity(player_entity)
+                        .insert(Empowered(Timer::from_seconds(EMPOWERED_SECS, TimerMode::Once)));
+                    cooldown.reset();
+                }
+            }
+        }
+
+        // The lamp comes back up as the shrine recharges
+        for child in children.iter() {
+            if let Ok(mut light) = light_query.get_mut(*child) {
+                light.intensity = if cooldown.finished() { SHRINE_LIGHT_INTENSITY } else { SHRINE_LIGHT_INTENSITY * 0.1 * cooldown.fraction() };
+            }
+        }
+    }
+}
+
+fn update_empowered(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut player_query: Query<(Entity, &Transform, &mut Empowered)>,
+    mut gizmos: Gizmos,
+) {
+    for (entity, transform, mut empowered) in &mut player_query {
+        empowered.0.tick(time.delta());
+        if empowered.0.finished() {
+            commands.entity(entity).remove::<Empowered>();
+            continue;
+        }
+        let center = Vec3::new(transform.translation.x, 0.05, transform.translation.z);
+        gizmos.circle(
+            Isometry3d::new(center, Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+            1.0,
+            Color::srgb(1.0, 0.85, 0.4),
+        );
+    }
+}
+
+fn vent_chimneys(
+    mut commands: Commands,
+    time: Res<Time>,
+    prop_assets: Res<PropAssets>,
+    layout: Res<LaneLayout>,
+    mut chimney_query: Query<(&Transform, &mut Interactive)>,
+) {
+    for (transform, mut interactive) in &mut chimney_query {
+        let Interactive::Chimney { vent } = interactive.as_mut() else {
+            continue;
+        };
+        if !vent.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let pos = transform.translation;
+        commands.spawn((
+            Prop,
+            LaneSegmentId(layout.index_at(pos.x)),
+            Smoke {
+                velocity: Vec3::Z * -pos.z.signum() * SMOKE_DRIFT_SPEED,
+                timer: Timer::from_seconds(SMOKE_LIFETIME_SECS, TimerMode::Once),
+            },
+            Mesh3d(prop_assets.smoke_mesh.clone()),
+            MeshMaterial3d(prop_assets.smoke_material.clone()),
+            Transform::from_translation(pos + Vec3::Y * 1.5).with_scale(Vec3::ZERO),
+            Visibility::Visible,
+            InheritedVisibility::default(),
+        ));
+    }
+}
+
+/// Smoke swells, drifts over the lane and hurts anyone inside it.
+#[allow(clippy::type_complexity)]
+fn update_smoke(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut smoke_query: Query<(Entity, &mut Transform, &mut Smoke), (Without<Player>, Without<Enemy>)>,
+    mut players: PlayerTargets,
+    mut enemies: EnemyTargets,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    let damage = SMOKE_DAMAGE_PER_SEC * time.delta_secs();
+
+    for (entity, mut transform, mut smoke) in &mut smoke_query {
+        smoke.timer.tick(time.delta());
+        if smoke.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation += smoke.velocity * time.delta_secs();
+
+        // Grow quickly, then thin out toward the end
+        let t = smoke.timer.fraction();
+        let radius = SMOKE_RADIUS * (t * 4.0).min(1.0) * (1.0 - t * t);
+        transform.scale = Vec3::splat(radius);
+
+        let center = transform.translation.xz();
+        hurt(&mut players, &mut enemies, &mut damage_events, DamageKind::OverTime, damage, |_, pos| pos.xz().distance(center) < radius);
+    }
+}
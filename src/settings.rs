@@ -0,0 +1,470 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{AbilitySettings, ControlScheme, GameState, PlayState};
+use crate::audio::AudioSettings;
+use crate::camera::CameraSettings;
+use crate::feel::GameFeelSettings;
+use crate::graphics::GraphicsSettings;
+use crate::input::{Action, ActionMap, BindingsMenu};
+use crate::particles::ParticleSettings;
+
+/// Where settings are kept: a localStorage key on the web, a file under the
+/// user's config directory on native.
+#[cfg(target_arch = "wasm32")]
+const STORAGE_KEY: &str = "league-wasm-game.settings";
+#[cfg(not(target_arch = "wasm32"))]
+const SETTINGS_DIR: &str = "league-wasm-game";
+#[cfg(not(target_arch = "wasm32"))]
+const SETTINGS_FILE: &str = "settings.ron";
+
+const VOLUME_STEPS: [f32; 6] = [0.0, 0.2, 0.4, 0.6, 0.8, 1.0];
+const RENDER_SCALE_STEPS: [f32; 3] = [0.5, 0.75, 1.0];
+const PARTICLE_DENSITY_STEPS: [f32; 3] = [0.25, 0.5, 1.0];
+const PAN_SPEED_STEPS: [f32; 4] = [0.5, 1.0, 1.5, 2.0];
+
+/// Everything saved between sessions. Each part is the resource its own
+/// module reads; missing fields load as their defaults.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct SavedSettings {
+    audio: AudioSettings,
+    graphics: GraphicsSettings,
+    particles: ParticleSettings,
+    camera: CameraSettings,
+    controls: ControlScheme,
+    abilities: AbilitySettings,
+    feel: GameFeelSettings,
+    bindings: ActionMap,
+}
+
+/// Whether the settings screen is showing, over the menu or pause menu.
+#[derive(Resource, Default)]
+pub struct SettingsMenu {
+    pub open: bool,
+}
+
+#[derive(Component)]
+pub struct SettingsUI;
+
+/// One line on the settings screen; pressing it steps to the next value.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SettingOption {
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    Shadows,
+    RenderScale,
+    ParticleDensity,
+    StartLocked,
+    EdgeScroll,
+    PanSpeed,
+    Controls,
+    BlinkCast,
+    CameraShake,
+    HitStop,
+    HitFlash,
+    LowHealthVignette,
+}
+
+impl SettingOption {
+    fn name(self) -> &'static str {
+        match self {
+            SettingOption::MasterVolume => "Master volume",
+            SettingOption::MusicVolume => "Music volume",
+            SettingOption::SfxVolume => "Effects volume",
+            SettingOption::Shadows => "Shadows",
+            SettingOption::RenderScale => "Render scale",
+            SettingOption::ParticleDensity => "Particles",
+            SettingOption::StartLocked => "Camera starts locked",
+            SettingOption::EdgeScroll => "Edge scrolling",
+            SettingOption::PanSpeed => "Pan speed",
+            SettingOption::Controls => "Movement",
+            SettingOption::BlinkCast => "Blink",
+            SettingOption::CameraShake => "Screen shake",
+            SettingOption::HitStop => "Hit pause",
+            SettingOption::HitFlash => "Hit flashes",
+            SettingOption::LowHealthVignette => "Low health vignette",
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct SettingLabel(SettingOption);
+
+#[derive(Component)]
+pub struct KeyBindingsButton;
+
+#[derive(Component)]
+pub struct SettingsBackButton;
+
+/// Every resource the settings screen edits.
+#[derive(SystemParam)]
+pub struct Settings<'w> {
+    audio: ResMut<'w, AudioSettings>,
+    graphics: ResMut<'w, GraphicsSettings>,
+    particles: ResMut<'w, ParticleSettings>,
+    camera: ResMut<'w, CameraSettings>,
+    controls: ResMut<'w, ControlScheme>,
+    abilities: ResMut<'w, AbilitySettings>,
+    feel: ResMut<'w, GameFeelSettings>,
+}
+
+impl Settings<'_> {
+    fn label(&self, option: SettingOption) -> String {
+        let percent = |value: f32| format!("{:.0}%", value * 100.0);
+        let on_off = |value: bool| if value { "On" } else { "Off" }.to_string();
+        let value = match option {
+            SettingOption::MasterVolume => percent(self.audio.master),
+            SettingOption::MusicVolume => percent(self.audio.music),
+            SettingOption::SfxVolume => percent(self.audio.sfx),
+            SettingOption::Shadows => on_off(self.graphics.shadows),
+            SettingOption::RenderScale => percent(self.graphics.render_scale),
+            SettingOption::ParticleDensity => percent(self.particles.density),
+            SettingOption::StartLocked => on_off(self.camera.start_locked),
+            SettingOption::EdgeScroll => on_off(self.camera.edge_scroll),
+            SettingOption::PanSpeed => format!("x{:.1}", self.camera.pan_speed),
+            SettingOption::Controls => self.controls.label().to_string(),
+            SettingOption::BlinkCast => {
+                if self.abilities.blink_on_release { "On release" } else { "On press" }.to_string()
+            }
+            SettingOption::CameraShake => on_off(self.feel.camera_shake),
+            SettingOption::HitStop => on_off(self.feel.hit_stop),
+            SettingOption::HitFlash => on_off(self.feel.hit_flash),
+            SettingOption::LowHealthVignette => on_off(self.feel.low_health_vignette),
+        };
+        format!("{}: {}", option.name(), value)
+    }
+
+    fn cycle(&mut self, option: SettingOption) {
+        match option {
+            SettingOption::MasterVolume => self.audio.master = next_step(self.audio.master, &VOLUME_STEPS),
+            SettingOption::MusicVolume => self.audio.music = next_step(self.audio.music, &VOLUME_STEPS),
+            SettingOption::SfxVolume => self.audio.sfx = next_step(self.audio.sfx, &VOLUME_STEPS),
+            SettingOption::Shadows => self.graphics.shadows = !self.graphics.shadows,
+            SettingOption::RenderScale => {
+                self.graphics.render_scale = next_step(self.graphics.render_scale, &RENDER_SCALE_STEPS);
+            }
+            SettingOption::ParticleDensity => {
+                self.particles.density = next_step(self.particles.density, &PARTICLE_DENSITY_STEPS);
+            }
+            SettingOption::StartLocked => self.camera.start_locked = !self.camera.start_locked,
+            SettingOption::EdgeScroll => self.camera.edge_scroll = !self.camera.edge_scroll,
+            SettingOption::PanSpeed => self.camera.pan_speed = next_step(self.camera.pan_speed, &PAN_SPEED_STEPS),
+            SettingOption::Controls => *self.controls = self.controls.next(),
+            SettingOption::BlinkCast => self.abilities.blink_on_release = !self.abilities.blink_on_release,
+            SettingOption::CameraShake => self.feel.camera_shake = !self.feel.camera_shake,
+            SettingOption::HitStop => self.feel.hit_stop = !self.feel.hit_stop,
+            SettingOption::HitFlash => self.feel.hit_flash = !self.feel.hit_flash,
+            SettingOption::LowHealthVignette => self.feel.low_health_vignette = !self.feel.low_health_vignette,
+        }
+    }
+}
+
+/// The first step above `value`, wrapping round to the lowest.
+fn next_step(value: f32, steps: &[f32]) -> f32 {
+    steps.iter().copied().find(|step| *step > value + 0.01).unwrap_or(steps[0])
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SettingsMenu>()
+            .add_systems(Startup, load_settings)
+            .add_systems(Update, save_settings.run_if(
+                edited::<AudioSettings>
+                    .or(edited::<GraphicsSettings>)
+                    .or(edited::<ParticleSettings>)
+                    .or(edited::<CameraSettings>)
+                    .or(edited::<ControlScheme>)
+                    .or(edited::<AbilitySettings>)
+                    .or(edited::<GameFeelSettings>)
+                    .or(edited::<ActionMap>),
+            ))
+            .add_systems(Update, (
+                close_settings,
+                settings_interaction,
+                update_settings_labels,
+                sync_settings_menu,
+            ).chain())
+            .add_systems(OnExit(GameState::Menu), hide_settings)
+            .add_systems(OnExit(PlayState::Paused), hide_settings);
+    }
+}
+
+fn load_settings(mut commands: Commands) {
+    let Some(contents) = read_stored() else {
+        return;
+    };
+    match ron::from_str::<SavedSettings>(&contents) {
+        Ok(mut saved) => {
+            saved.bindings.fill_defaults();
+            commands.insert_resource(saved.audio);
+            commands.insert_resource(saved.graphics);
+            commands.insert_resource(saved.particles);
+            commands.insert_resource(saved.camera);
+            commands.insert_resource(saved.controls);
+            commands.insert_resource(saved.abilities);
+            commands.insert_resource(saved.feel);
+            commands.insert_resource(saved.bindings);
+        }
+        Err(err) => warn!("Ignoring saved settings: {err}"),
+    }
+}
+
+/// Run condition: `T` changed, other than by being created or loaded at
+/// startup, so launching the game doesn't rewrite the saved settings.
+fn edited<T: Resource>(res: Option<Res<T>>) -> bool {
+    res.is_some_and(|res| res.is_changed() && !res.is_added())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn save_settings(
+    audio: Res<AudioSettings>,
+    graphics: Res<GraphicsSettings>,
+    particles: Res<ParticleSettings>,
+    camera: Res<CameraSettings>,
+    controls: Res<ControlScheme>,
+    abilities: Res<AbilitySettings>,
+    feel: Res<GameFeelSettings>,
+    bindings: Res<ActionMap>,
+) {
+    let saved = SavedSettings {
+        audio: audio.clone(),
+        graphics: graphics.clone(),
+        particles: particles.clone(),
+        camera: camera.clone(),
+        controls: *controls,
+        abilities: abilities.clone(),
+        feel: feel.clone(),
+        bindings: bindings.clone(),
+    };
+    let result = ron::ser::to_string_pretty(&saved, ron::ser::PrettyConfig::default())
+        .map_err(|err| err.to_string())
+        .and_then(|contents| write_stored(&contents));
+    if let Err(err) = result {
+        warn!("Could not save settings: {err}");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_stored() -> Option<String> {
+    web_sys::window()?.local_storage().ok()??.get_item(STORAGE_KEY).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_stored(contents: &str) -> Result<(), String> {
+    let storage = web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or("localStorage is unavailable")?;
+    storage.set_item(STORAGE_KEY, contents).map_err(|err| format!("{err:?}"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn settings_path() -> Option<std::path::PathBuf> {
+    use std::path::PathBuf;
+
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join(SETTINGS_DIR).join(SETTINGS_FILE))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_stored() -> Option<String> {
+    std::fs::read_to_string(settings_path()?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_stored(contents: &str) -> Result<(), String> {
+    let path = settings_path().ok_or("no config directory")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    std::fs::write(&path, contents).map_err(|err| err.to_string())
+}
+
+/// The pause action backs out of the settings screen. It is consumed so the
+/// pause menu doesn't also see it and resume.
+pub fn close_settings(
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut mouse: ResMut<ButtonInput<MouseButton>>,
+    map: Res<ActionMap>,
+    mut menu: ResMut<SettingsMenu>,
+    bindings_menu: Res<BindingsMenu>,
+) {
+    if menu.open && !bindings_menu.open && map.just_pressed(Action::Pause, &keys, &mouse) {
+        map.consume(Action::Pause, &mut keys, &mut mouse);
+        menu.open = false;
+    }
+}
+
+fn hide_settings(mut menu: ResMut<SettingsMenu>) {
+    menu.open = false;
+}
+
+#[allow(clippy::type_complexity)]
+fn settings_interaction(
+    mut menu: ResMut<SettingsMenu>,
+    mut bindings_menu: ResMut<BindingsMenu>,
+    mut settings: Settings,
+    option_query: Query<(&Interaction, &SettingOption), (Changed<Interaction>, With<Button>)>,
+    bindings_query: Query<&Interaction, (Changed<Interaction>, With<KeyBindingsButton>)>,
+    back_query: Query<&Interaction, (Changed<Interaction>, With<SettingsBackButton>)>,
+) {
+    for (interaction, option) in &option_query {
+        if *interaction == Interaction::Pressed {
+            settings.cycle(*option);
+        }
+    }
+    if bindings_query.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        bindings_menu.open = true;
+    }
+    if back_query.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        menu.open = false;
+    }
+}
+
+fn update_settings_labels(settings: Settings, mut label_query: Query<(&SettingLabel, &mut Text)>) {
+    for (label, mut text) in &mut label_query {
+        let wanted = settings.label(label.0);
+        if text.0 != wanted {
+            text.0 = wanted;
+        }
+    }
+}
+
+/// Shows or hides the settings screen to match `SettingsMenu`.
+fn sync_settings_menu(
+    mut commands: Commands,
+    menu: Res<SettingsMenu>,
+    ui_query: Query<Entity, With<SettingsUI>>,
+) {
+    if !menu.is_changed() {
+        return;
+    }
+    for entity in &ui_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    if menu.open {
+        setup_settings_menu(&mut commands);
+    }
+}
+
+fn setup_settings_menu(commands: &mut Commands) {
+    let columns: [&[(&str, &[SettingOption])]; 2] = [
+        &[
+            ("Audio", &[SettingOption::MasterVolume, SettingOption::MusicVolume, SettingOption::SfxVolume]),
+            ("Graphics", &[SettingOption::Shadows, SettingOption::RenderScale, SettingOption::ParticleDensity]),
+            ("Camera", &[SettingOption::StartLocked, SettingOption::EdgeScroll, SettingOption::PanSpeed]),
+        ],
+        &[
+            ("Controls", &[SettingOption::Controls, SettingOption::BlinkCast]),
+            ("Accessibility", &[
+                SettingOption::CameraShake,
+                SettingOption::HitStop,
+                SettingOption::HitFlash,
+                SettingOption::LowHealthVignette,
+            ]),
+        ],
+    ];
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.9)),
+        // Over the menus it opens from, and keeps clicks off their buttons
+        ZIndex(20),
+        FocusPolicy::Block,
+        SettingsUI,
+    )).with_children(|parent| {
+        parent.spawn((
+            Text::new("SETTINGS"),
+            TextFont::from_font_size(60.0),
+            TextColor(Color::WHITE),
+        ));
+
+        parent.spawn(Node {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::FlexStart,
+            ..default()
+        }).with_children(|row| {
+            for sections in columns {
+                row.spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    margin: UiRect::horizontal(Val::Px(20.0)),
+                    ..default()
+                }).with_children(|column| {
+                    for (title, options) in sections {
+                        column.spawn((
+                            Text::new(*title),
+                            TextFont::from_font_size(24.0),
+                            TextColor(Color::srgb(0.8, 0.8, 0.0)),
+                            Node {
+                                margin: UiRect::top(Val::Px(12.0)),
+                                ..default()
+                            },
+                        ));
+                        for option in *options {
+                            column.spawn((
+                                Button,
+                                Node {
+                                    width: Val::Px(360.0),
+                                    height: Val::Px(38.0),
+                                    margin: UiRect::top(Val::Px(6.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+                                *option,
+                            )).with_child((Text::default(), TextFont::from_font_size(20.0), SettingLabel(*option)));
+                            // Rebinding has its own screen, opened from under the movement scheme
+                            if *option == SettingOption::Controls {
+                                column.spawn((
+                                    Button,
+                                    Node {
+                                        width: Val::Px(360.0),
+                                        height: Val::Px(38.0),
+                                        margin: UiRect::top(Val::Px(6.0)),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+                                    KeyBindingsButton,
+                                )).with_child((Text::new("Key bindings..."), TextFont::from_font_size(20.0)));
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        parent.spawn((
+            Button,
+            Node {
+                width: Val::Px(250.0),
+                height: Val::Px(60.0),
+                margin: UiRect::all(Val::Px(20.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+            SettingsBackButton,
+        )).with_child((Text::new("BACK"), TextFont::from_font_size(30.0)));
+    });
+}
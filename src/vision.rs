@@ -0,0 +1,203 @@
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::mesh::VertexAttributeValues;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+use crate::app::{Dying, Enemy, GameState, PlayState, Player, Projectile};
+use crate::lane::LaneLayout;
+use crate::weather::CurrentWeather;
+
+/// How far the player sees in clear weather.
+pub const VISION_RADIUS: f32 = 24.0;
+pub const VISION_CELL_SIZE: f32 = 1.5;
+/// Opacity of the ground overlay over cells the player cannot see.
+const FOG_ALPHA: u8 = 170;
+/// The overlay reaches this many grid widths out; past the grid the
+/// clamped edge texels keep everything fogged.
+const OVERLAY_SCALE: f32 = 8.0;
+/// Unseen projectiles shrink away at this rate per second.
+const PROJECTILE_FADE_RATE: f32 = 6.0;
+
+/// Which ground cells the player can currently see. Plain data computed from
+/// the lane layout, so AI and tests can use it without rendering.
+#[derive(Resource, Clone, Debug)]
+pub struct VisionGrid {
+    /// World X/Z of the corner of cell (0, 0).
+    pub origin: Vec2,
+    pub cell_size: f32,
+    pub size: UVec2,
+    pub visible: Vec<bool>,
+}
+
+impl Default for VisionGrid {
+    fn default() -> Self {
+        Self::new(VISION_RADIUS, VISION_CELL_SIZE)
+    }
+}
+
+impl VisionGrid {
+    /// A grid large enough to hold a vision circle of `radius`.
+    pub fn new(radius: f32, cell_size: f32) -> Self {
+        let cells = (2.0 * radius / cell_size).ceil() as u32 + 2;
+        Self {
+            origin: Vec2::ZERO,
+            cell_size,
+            size: UVec2::splat(cells),
+            visible: vec![false; (cells * cells) as usize],
+        }
+    }
+
+    pub fn world_size(&self) -> Vec2 {
+        self.size.as_vec2() * self.cell_size
+    }
+
+    /// Recentres the grid on `eye` and marks every cell within `radius` that no building hides.
+    pub fn compute(&mut self, layout: &LaneLayout, eye: Vec3, radius: f32) {
+        // Snap to whole cells so the overlay doesn't swim as the eye moves
+        self.origin = ((eye.xz() - self.world_size() / 2.0) / self.cell_size).floor() * self.cell_size;
+
+        for iz in 0..self.size.y {
+            for ix in 0..self.size.x {
+                let center = self.origin + (UVec2::new(ix, iz).as_vec2() + 0.5) * self.cell_size;
+                let world = Vec3::new(center.x, eye.y, center.y);
+                self.visible[(iz * self.size.x + ix) as usize] =
+                    center.distance(eye.xz()) <= radius && !layout.sight_blocked(eye, world);
+            }
+        }
+    }
+
+    pub fn cell_at(&self, pos: Vec3) -> Option<usize> {
+        let cell = ((pos.xz() - self.origin) / self.cell_size).floor();
+        if cell.x < 0.0 || cell.y < 0.0 || cell.x >= self.size.x as f32 || cell.y >= self.size.y as f32 {
+            return None;
+        }
+        Some(cell.y as usize * self.size.x as usize + cell.x as usize)
+    }
+
+    pub fn is_visible(&self, pos: Vec3) -> bool {
+        self.cell_at(pos).is_some_and(|index| self.visible[index])
+    }
+}
+
+/// Dark ground overlay drawn from the vision grid.
+#[derive(Component)]
+pub struct FogOverlay {
+    image: Handle<Image>,
+}
+
+pub struct VisionPlugin;
+
+impl Plugin for VisionPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<VisionGrid>()
+            .add_systems(OnEnter(GameState::Playing), setup_fog_overlay)
+            .add_systems(Update, (
+                update_vision,
+                hide_unseen,
+                update_fog_overlay,
+            ).chain().run_if(in_state(PlayState::Running)))
+            .add_systems(OnExit(GameState::Playing), cleanup_fog_overlay);
+    }
+}
+
+fn setup_fog_overlay(
+    mut commands: Commands,
+    grid: Res<VisionGrid>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // One texel per cell; linear filtering softens the edge of the vision circle
+    let mut image = Image::new_fill(
+        Extent3d { width: grid.size.x, height: grid.size.y, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        &[0, 0, 0, FOG_ALPHA],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = ImageSampler::linear();
+    let image = images.add(image);
+
+    let size = grid.world_size() * OVERLAY_SCALE;
+    let mut mesh = Plane3d::default().mesh().size(size.x, size.y).build();
+    if let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0) {
+        for uv in uvs {
+            uv[0] = 0.5 + (uv[0] - 0.5) * OVERLAY_SCALE;
+            uv[1] = 0.5 + (uv[1] - 0.5) * OVERLAY_SCALE;
+        }
+    }
+
+    commands.spawn((
+        FogOverlay { image: image.clone() },
+        Mesh3d(meshes.add(mesh)),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color_texture: Some(image),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        })),
+        Transform::from_xyz(0.0, 0.07, 0.0),
+        Visibility::Visible,
+        InheritedVisibility::default(),
+    ));
+}
+
+fn cleanup_fog_overlay(mut commands: Commands, query: Query<Entity, With<FogOverlay>>) {
+    for entity in &query {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn update_vision(
+    layout: Res<LaneLayout>,
+    weather: Res<CurrentWeather>,
+    mut grid: ResMut<VisionGrid>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    if let Ok(transform) = player_query.get_single() {
+        let eye = transform.translation + Vec3::Y * 1.5;
+        grid.compute(&layout, eye, VISION_RADIUS * weather.0.def().vision_scale);
+    }
+}
+
+/// Hides enemies outside vision and fades out projectiles that leave it.
+#[allow(clippy::type_complexity)]
+fn hide_unseen(
+    time: Res<Time>,
+    grid: Res<VisionGrid>,
+    mut enemy_query: Query<(&Transform, &mut Visibility), Or<(With<Enemy>, With<Dying>)>>,
+    mut projectile_query: Query<&mut Transform, (With<Projectile>, Without<Enemy>, Without<Dying>)>,
+) {
+    for (transform, mut visibility) in &mut enemy_query {
+        let wanted = if grid.is_visible(transform.translation) { Visibility::Visible } else { Visibility::Hidden };
+        visibility.set_if_neq(wanted);
+    }
+
+    let step = PROJECTILE_FADE_RATE * time.delta_secs();
+    for mut transform in &mut projectile_query {
+        let target = if grid.is_visible(transform.translation) { 1.0 } else { 0.0 };
+        let scale = transform.scale.x + (target - transform.scale.x).clamp(-step, step);
+        transform.scale = Vec3::splat(scale);
+    }
+}
+
+fn update_fog_overlay(
+    grid: Res<VisionGrid>,
+    mut images: ResMut<Assets<Image>>,
+    mut overlay_query: Query<(&FogOverlay, &mut Transform)>,
+) {
+    for (overlay, mut transform) in &mut overlay_query {
+        let center = grid.origin + grid.world_size() / 2.0;
+        transform.translation.x = center.x;
+        transform.translation.z = center.y;
+
+        let Some(image) = images.get_mut(&overlay.image) else {
+            continue;
+        };
+        for (texel, visible) in image.data.chunks_exact_mut(4).zip(&grid.visible) {
+            texel[3] = if *visible { 0 } else { FOG_ALPHA };
+        }
+    }
+}
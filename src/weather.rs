@@ -0,0 +1,244 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::app::{GameState, PlayState, Player, RunSeed};
+use crate::biome::Biome;
+use crate::lane::LaneLayout;
+
+/// Particles live in a box this size around the player.
+const PARTICLE_AREA: Vec3 = Vec3::new(60.0, 20.0, 50.0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Weather {
+    Clear,
+    Rain,
+    Fog,
+    Sandstorm,
+}
+
+impl Weather {
+    pub const ALL: [Weather; 4] = [Weather::Clear, Weather::Rain, Weather::Fog, Weather::Sandstorm];
+
+    pub fn def(self) -> &'static WeatherDef {
+        match self {
+            Weather::Clear => &CLEAR,
+            Weather::Rain => &RAIN,
+            Weather::Fog => &FOG,
+            Weather::Sandstorm => &SANDSTORM,
+        }
+    }
+
+    /// Weather over lane segment `index`, rolled once per biome section from
+    /// that biome's odds. The opening section is always clear.
+    pub fn for_segment(seed: u64, index: i32) -> Weather {
+        let section = Biome::section(index);
+        if section <= 0 {
+            return Weather::Clear;
+        }
+
+        let weights = Biome::for_segment(seed, index).def().weather_weights;
+        let mut rng = StdRng::seed_from_u64(seed.rotate_left(29) ^ section as u64);
+        let mut roll = rng.gen_range(0..weights.iter().sum::<u32>());
+        for (weather, weight) in Self::ALL.into_iter().zip(weights) {
+            if roll < weight {
+                return weather;
+            }
+            roll -= weight;
+        }
+        Weather::Clear
+    }
+}
+
+/// Look of the particles a weather fills the air with.
+pub struct WeatherParticles {
+    pub count: usize,
+    pub size: Vec3,
+    pub color: Color,
+    /// Velocity before wind.
+    pub velocity: Vec3,
+}
+
+pub struct WeatherDef {
+    pub name: &'static str,
+    /// Multiplies the biome's fog distances; lower is thicker.
+    pub fog_scale: f32,
+    /// Multiplies the biome's fog colour.
+    pub fog_tint: Color,
+    /// Multiplies how far enemies can see the player from.
+    pub aggro_scale: f32,
+    /// Multiplies how far the player can see.
+    pub vision_scale: f32,
+    /// Multiplies projectile speed.
+    pub projectile_speed_scale: f32,
+    /// Constant drift, in units per second, added to the velocity of
+    /// projectiles and weather particles.
+    pub wind: Vec3,
+    pub particles: Option<WeatherParticles>,
+}
+
+static CLEAR: WeatherDef = WeatherDef {
+    name: "Clear",
+    fog_scale: 1.0,
+    fog_tint: Color::WHITE,
+    aggro_scale: 1.0,
+    vision_scale: 1.0,
+    projectile_speed_scale: 1.0,
+    wind: Vec3::ZERO,
+    particles: None,
+};
+
+static RAIN: WeatherDef = WeatherDef {
+    name: "Rain",
+    fog_scale: 0.8,
+    fog_tint: Color::srgb(0.75, 0.8, 0.9),
+    aggro_scale: 0.9,
+    vision_scale: 0.85,
+    projectile_speed_scale: 0.75,
+    wind: Vec3::new(0.0, 0.0, 1.5),
+    particles: Some(WeatherParticles {
+        count: 400,
+        size: Vec3::new(0.03, 0.6, 0.03),
+        color: Color::srgba(0.7, 0.8, 1.0, 0.5),
+        velocity: Vec3::new(0.0, -25.0, 0.0),
+    }),
+};
+
+static FOG: WeatherDef = WeatherDef {
+    name: "Fog",
+    fog_scale: 0.4,
+    fog_tint: Color::srgb(1.1, 1.1, 1.1),
+    aggro_scale: 0.6,
+    vision_scale: 0.55,
+    projectile_speed_scale: 1.0,
+    wind: Vec3::ZERO,
+    particles: None,
+};
+
+static SANDSTORM: WeatherDef = WeatherDef {
+    name: "Sandstorm",
+    fog_scale: 0.55,
+    fog_tint: Color::srgb(1.2, 0.95, 0.6),
+    aggro_scale: 0.75,
+    vision_scale: 0.7,
+    projectile_speed_scale: 0.9,
+    wind: Vec3::new(0.0, 0.0, 8.0),
+    particles: Some(WeatherParticles {
+        count: 300,
+        size: Vec3::splat(0.08),
+        color: Color::srgba(0.85, 0.7, 0.45, 0.7),
+        velocity: Vec3::new(2.0, -1.0, 0.0),
+    }),
+};
+
+#[derive(Resource)]
+pub struct CurrentWeather(pub Weather);
+
+impl Default for CurrentWeather {
+    fn default() -> Self {
+        Self(Weather::Clear)
+    }
+}
+
+#[derive(Component)]
+pub struct WeatherParticle;
+
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<CurrentWeather>()
+            .add_systems(OnEnter(GameState::Playing), reset_weather)
+            .add_systems(Update, (
+                update_current_weather,
+                move_weather_particles,
+            ).chain().run_if(in_state(PlayState::Running)))
+            .add_systems(OnExit(GameState::Playing), cleanup_weather);
+    }
+}
+
+fn reset_weather(mut current: ResMut<CurrentWeather>) {
+    current.0 = Weather::Clear;
+}
+
+fn cleanup_weather(mut commands: Commands, query: Query<Entity, With<WeatherParticle>>) {
+    for entity in &query {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Switches weather as the player crosses into a new section and swaps the particle pool.
+#[allow(clippy::too_many_arguments)]
+fn update_current_weather(
+    mut commands: Commands,
+    run_seed: Res<RunSeed>,
+    layout: Res<LaneLayout>,
+    mut current: ResMut<CurrentWeather>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    player_query: Query<&Transform, With<Player>>,
+    particle_query: Query<Entity, With<WeatherParticle>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let weather = Weather::for_segment(run_seed.0, layout.index_at(player_transform.translation.x));
+    if weather == current.0 {
+        return;
+    }
+    current.0 = weather;
+
+    for entity in &particle_query {
+        commands.entity(entity).despawn();
+    }
+    let Some(particles) = &weather.def().particles else {
+        return;
+    };
+
+    let mesh = meshes.add(Cuboid::from_size(particles.size));
+    let material = materials.add(StandardMaterial {
+        base_color: particles.color,
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+    let mut rng = rand::thread_rng();
+    for _ in 0..particles.count {
+        let offset = Vec3::new(
+            rng.gen_range(-0.5..0.5) * PARTICLE_AREA.x,
+            rng.gen_range(0.0..1.0) * PARTICLE_AREA.y,
+            rng.gen_range(-0.5..0.5) * PARTICLE_AREA.z,
+        );
+        commands.spawn((
+            WeatherParticle,
+            Mesh3d(mesh.clone()),
+            MeshMaterial3d(material.clone()),
+            Transform::from_translation(player_transform.translation.with_y(0.0) + offset),
+            Visibility::Visible,
+            InheritedVisibility::default(),
+        ));
+    }
+}
+
+/// Moves particles with the weather and wraps them around the player so the box follows along.
+fn move_weather_particles(
+    time: Res<Time>,
+    current: Res<CurrentWeather>,
+    player_query: Query<&Transform, (With<Player>, Without<WeatherParticle>)>,
+    mut particle_query: Query<&mut Transform, With<WeatherParticle>>,
+) {
+    let def = current.0.def();
+    let (Some(particles), Ok(player_transform)) = (&def.particles, player_query.get_single()) else {
+        return;
+    };
+
+    let velocity = particles.velocity + def.wind;
+    let center = player_transform.translation.with_y(PARTICLE_AREA.y / 2.0);
+    for mut transform in &mut particle_query {
+        transform.translation += velocity * time.delta_secs();
+        let offset = transform.translation - center;
+        transform.translation = center + (offset + PARTICLE_AREA / 2.0).rem_euclid(PARTICLE_AREA) - PARTICLE_AREA / 2.0;
+    }
+}